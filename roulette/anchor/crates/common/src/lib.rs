@@ -0,0 +1,4 @@
+//! Code shared by the roulette, mines and spinwheel programs.
+
+pub mod limits;
+pub mod token;
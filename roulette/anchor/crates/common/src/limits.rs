@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+pub const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+pub const LIMIT_LOOSENING_DELAY: i64 = SECONDS_PER_DAY; // loosened player limits wait this long
+
+pub const MAX_CURRENCIES: usize = 4; // currencies a player can set limits in
+
+/// Loss limits and max bet in one currency, in its base units; the default mint is SOL
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CurrencyLimits {
+    pub mint: Pubkey,
+    pub daily_loss_limit: u64,  // 0 = no limit
+    pub weekly_loss_limit: u64, // 0 = no limit
+    pub max_bet: u64,           // 0 = no limit
+}
+
+impl CurrencyLimits {
+    pub const LEN: usize = 32 + 8 + 8 + 8;
+
+    fn is_limited(&self) -> bool {
+        self.daily_loss_limit != 0 || self.weekly_loss_limit != 0 || self.max_bet != 0
+    }
+}
+
+/// Limits a player sets on their own play. Loss limits and the max bet are per currency;
+/// once any currency is limited, bets in currencies without an entry are refused.
+/// Self-exclusion and the cooldown apply to every bet.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub struct LimitSettings {
+    pub currencies: Vec<CurrencyLimits>, // at most MAX_CURRENCIES, one per mint
+    pub session_cooldown: i64,           // seconds required between games
+    pub excluded_until: i64,             // unix timestamp; no play before it
+}
+
+impl LimitSettings {
+    pub const LEN: usize = 4 + MAX_CURRENCIES * CurrencyLimits::LEN + 8 + 8;
+
+    pub fn limits_for(&self, mint: Pubkey) -> Option<&CurrencyLimits> {
+        self.currencies.iter().find(|limits| limits.mint == mint)
+    }
+
+    fn any_limited(&self) -> bool {
+        self.currencies.iter().any(CurrencyLimits::is_limited)
+    }
+
+    fn validate(&self) -> std::result::Result<(), LimitViolation> {
+        if self.currencies.len() > MAX_CURRENCIES {
+            return Err(LimitViolation::InvalidSettings);
+        }
+        for (i, limits) in self.currencies.iter().enumerate() {
+            if self.currencies[..i].iter().any(|other| other.mint == limits.mint) {
+                return Err(LimitViolation::InvalidSettings);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Net amount lost in one currency; negative when ahead
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CurrencyLoss {
+    pub mint: Pubkey,
+    pub day_loss: i64,
+    pub week_loss: i64,
+}
+
+impl CurrencyLoss {
+    pub const LEN: usize = 32 + 8 + 8;
+}
+
+/// A player's limits and the net losses they are checked against.
+/// Losses are counted per currency, per day and per week since the unix epoch,
+/// for the currencies the settings list.
+/// Each program stores this in its own `PlayerLimits` account.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Default)]
+pub struct LimitState {
+    pub settings: LimitSettings, // in force
+    pub pending: LimitSettings,  // requested loosening, waiting out LIMIT_LOOSENING_DELAY
+    pub pending_at: i64,         // when `pending` takes over; 0 when nothing is pending
+    pub day: i64,                // day index the daily losses belong to
+    pub week: i64,               // week index the weekly losses belong to
+    pub losses: Vec<CurrencyLoss>, // one per listed currency that has been played
+    pub last_play_at: i64,
+}
+
+/// Why a request or a new game breaks the player's limits; each program maps it to its own error
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LimitViolation {
+    SelfExcluded,
+    CooldownActive,
+    AboveBetLimit,
+    LossLimitReached,
+    CurrencyNotLimited,
+    InvalidSettings,
+    Overflow,
+}
+
+impl LimitState {
+    pub const LEN: usize = LimitSettings::LEN * 2 + 8 + 8 + 8 + 4 + MAX_CURRENCIES * CurrencyLoss::LEN + 8;
+
+    /// Applies a loosening whose delay has passed and starts new loss windows when the day or week turns
+    pub fn refresh(&mut self, now: i64) {
+        if self.pending_at != 0 && now >= self.pending_at {
+            self.settings = self.pending.clone();
+            self.pending_at = 0;
+            self.drop_unlisted_losses();
+        }
+        if now / SECONDS_PER_DAY != self.day {
+            self.day = now / SECONDS_PER_DAY;
+            self.losses.iter_mut().for_each(|loss| loss.day_loss = 0);
+        }
+        if now / SECONDS_PER_WEEK != self.week {
+            self.week = now / SECONDS_PER_WEEK;
+            self.losses.iter_mut().for_each(|loss| loss.week_loss = 0);
+        }
+    }
+
+    /// Tightened fields apply at once; if anything is loosened, the whole request waits
+    /// LIMIT_LOOSENING_DELAY. A new request replaces one still pending.
+    /// Listing a currency that is refused, or dropping a limited one, counts as loosening.
+    pub fn request(&mut self, now: i64, requested: LimitSettings) -> std::result::Result<(), LimitViolation> {
+        requested.validate()?;
+        self.refresh(now);
+        let current = &self.settings;
+        let mut currencies: Vec<CurrencyLimits> = requested
+            .currencies
+            .iter()
+            .filter_map(|wanted| match current.limits_for(wanted.mint) {
+                Some(limits) => Some(CurrencyLimits {
+                    mint: wanted.mint,
+                    daily_loss_limit: stricter_limit(limits.daily_loss_limit, wanted.daily_loss_limit),
+                    weekly_loss_limit: stricter_limit(limits.weekly_loss_limit, wanted.weekly_loss_limit),
+                    max_bet: stricter_limit(limits.max_bet, wanted.max_bet),
+                }),
+                None if current.any_limited() => None,
+                None => Some(*wanted),
+            })
+            .collect();
+        currencies.extend(
+            current
+                .currencies
+                .iter()
+                .filter(|limits| limits.is_limited() && requested.limits_for(limits.mint).is_none()),
+        );
+        self.settings = LimitSettings {
+            currencies,
+            session_cooldown: current.session_cooldown.max(requested.session_cooldown),
+            excluded_until: current.excluded_until.max(requested.excluded_until),
+        };
+        self.drop_unlisted_losses();
+        if self.settings == requested {
+            self.pending_at = 0;
+        } else {
+            self.pending = requested;
+            self.pending_at = now.saturating_add(LIMIT_LOOSENING_DELAY);
+        }
+        Ok(())
+    }
+
+    /// Checks a new game in `mint` against the limits and counts its `stake` as lost until paid back.
+    /// `largest_bet` is the biggest single bet in the game.
+    pub fn record_play(
+        &mut self,
+        now: i64,
+        mint: Pubkey,
+        stake: u64,
+        largest_bet: u64,
+    ) -> std::result::Result<(), LimitViolation> {
+        self.refresh(now);
+        let settings = &self.settings;
+        if now < settings.excluded_until {
+            return Err(LimitViolation::SelfExcluded);
+        }
+        if now < self.last_play_at.saturating_add(settings.session_cooldown) {
+            return Err(LimitViolation::CooldownActive);
+        }
+        if let Some(limits) = settings.limits_for(mint).copied() {
+            if limits.max_bet != 0 && largest_bet > limits.max_bet {
+                return Err(LimitViolation::AboveBetLimit);
+            }
+            let stake = i64::try_from(stake).map_err(|_| LimitViolation::Overflow)?;
+            let loss = self.loss_in(mint);
+            loss.day_loss = loss.day_loss.saturating_add(stake);
+            loss.week_loss = loss.week_loss.saturating_add(stake);
+            if !within_limit(limits.daily_loss_limit, loss.day_loss)
+                || !within_limit(limits.weekly_loss_limit, loss.week_loss)
+            {
+                return Err(LimitViolation::LossLimitReached);
+            }
+        } else if settings.any_limited() {
+            return Err(LimitViolation::CurrencyNotLimited);
+        }
+        self.last_play_at = now;
+        Ok(())
+    }
+
+    /// Credits `amount` of `mint` returned to the player against the current loss windows
+    pub fn record_payout(&mut self, now: i64, mint: Pubkey, amount: u64) {
+        self.refresh(now);
+        if let Some(loss) = self.losses.iter_mut().find(|loss| loss.mint == mint) {
+            let amount = i64::try_from(amount).unwrap_or(i64::MAX);
+            loss.day_loss = loss.day_loss.saturating_sub(amount);
+            loss.week_loss = loss.week_loss.saturating_sub(amount);
+        }
+    }
+
+    /// The loss counters for a listed currency, created on its first play
+    fn loss_in(&mut self, mint: Pubkey) -> &mut CurrencyLoss {
+        let index = match self.losses.iter().position(|loss| loss.mint == mint) {
+            Some(index) => index,
+            None => {
+                self.losses.push(CurrencyLoss {
+                    mint,
+                    ..CurrencyLoss::default()
+                });
+                self.losses.len() - 1
+            }
+        };
+        &mut self.losses[index]
+    }
+
+    /// Keeps the losses bounded by the listed currencies
+    fn drop_unlisted_losses(&mut self) {
+        let settings = &self.settings;
+        self.losses.retain(|loss| settings.limits_for(loss.mint).is_some());
+    }
+}
+
+/// The stricter of two limits where 0 means no limit
+fn stricter_limit(current: u64, requested: u64) -> u64 {
+    match (current, requested) {
+        (0, limit) | (limit, 0) => limit,
+        (current, requested) => current.min(requested),
+    }
+}
+
+/// Whether a net `loss` stays within `limit` (0 means no limit)
+fn within_limit(limit: u64, loss: i64) -> bool {
+    limit == 0 || loss <= 0 || loss as u64 <= limit
+}
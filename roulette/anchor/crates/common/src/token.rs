@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::Mint;
+
+/// Whether `mint` is a Token-2022 mint with a transfer fee. Such a mint delivers less than
+/// was sent on every transfer, so stakes and payouts would no longer add up.
+pub fn has_transfer_fee(mint: &AccountInfo) -> Result<bool> {
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(false);
+    }
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(mint.get_extension::<TransferFeeConfig>().is_ok())
+}
@@ -1,190 +1,1061 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::hash::hashv; // for simple commit-reveal hash
 use anchor_lang::system_program; // for system_program::transfer
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use casino_common::limits::{LimitSettings, LimitState, LimitViolation};
+use casino_common::token::has_transfer_fee;
 
 declare_id!("HEze64wGfroApJ15PMLJjWYbNWo9zB4cz9oTvJt2F4aj");
 
 // Constants
-const MAX_BOARD_SIZE: usize = 25;
-const MAX_MINES: usize = 24;
-const MERKLE_DEPTH: usize = 5; // ceil(log2(25)) = 5
-const DEFAULT_EXPIRY_SECS: i64 = 600; // 10 minutes timeout
+const MIN_BOARD_SIDE: u8 = 2;
+const MAX_BOARD_SIDE: u8 = 8; // 8x8 = 64 tiles, one bit each in a u64
+const MAX_MINES: usize = 63;
+const LEGACY_BOARD_SIDE: u8 = 5; // v1 games were always 5x5
+const LEGACY_BOARD_SIZE: usize = 25; // fixed tile arrays in v1 game accounts
+const GAME_VERSION: u8 = 2;
+// Anchor's default discriminator for the v1 layout, sha256("account:MinesGame")[..8]
+const GAME_V1_DISCRIMINATOR: [u8; 8] = [211, 190, 68, 246, 250, 142, 19, 119];
+const BOARD_REVEAL_SECS: i64 = 600; // house must open the board within 10 minutes of game end
+const BOARD_PENALTY_BPS: u128 = 10_000; // bankroll forfeits the stake again for a bad/missing board
+const MAX_HOUSE_EDGE_BPS: u16 = 1_000; // 10%
+const BPS_DENOMINATOR: u128 = 10_000;
 
 // Game state
 const STATE_COMMITTED: u8 = 0; // Using Merkle root; no global reveal step required
+const STATE_AWAITING_BOARD: u8 = 1; // game over; house must open every leaf via finalize_board
 const STATE_FINISHED: u8 = 2;
 
 #[program]
 pub mod mines {
     use super::*;
 
+    /// Creates the table config; only the bankroll authority can do this
+    pub fn initialize_config(ctx: Context<InitializeConfig>, house: Pubkey, house_edge_bps: u16) -> Result<()> {
+        require!(house_edge_bps <= MAX_HOUSE_EDGE_BPS, CustomError::InvalidHouseEdge);
+
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.house = house;
+        config.house_edge_bps = house_edge_bps;
+        config.min_bet = 1_000_000; // 0.001 SOL in lamports
+        config.max_bet = 1_000_000_000; // 1 SOL in lamports
+        config.max_mines = MAX_MINES as u8;
+        config.min_expiry_secs = 60;
+        config.max_expiry_secs = 3_600;
+        config.abandon_grace_secs = 600; // house waits 10 minutes past expiry before settling
+        config.max_payout = 100_000_000_000; // 100 SOL in lamports
+        config.reveal_response_secs = 120; // house opens a requested tile within 2 minutes
+        config.is_paused = false;
+        config.bump = ctx.bumps.config;
+
+        msg!("Mines config initialized for house {} with edge {} bps", house, house_edge_bps);
+        Ok(())
+    }
+
+    /// Updates table settings (house key, edge, bet limits, mine cap, pause state, game timeouts, payout cap,
+    /// house response window)
+    #[allow(clippy::too_many_arguments)]
+    pub fn update_settings(
+        ctx: Context<UpdateSettings>,
+        house: Option<Pubkey>,
+        house_edge_bps: Option<u16>,
+        min_bet: Option<u64>,
+        max_bet: Option<u64>,
+        max_mines: Option<u8>,
+        is_paused: Option<bool>,
+        min_expiry_secs: Option<i64>,
+        max_expiry_secs: Option<i64>,
+        abandon_grace_secs: Option<i64>,
+        max_payout: Option<u64>,
+        reveal_response_secs: Option<i64>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        // Active games keep the house and edge they started with
+        if let Some(house) = house {
+            config.house = house;
+        }
+
+        if let Some(edge) = house_edge_bps {
+            require!(edge <= MAX_HOUSE_EDGE_BPS, CustomError::InvalidHouseEdge);
+            config.house_edge_bps = edge;
+        }
+
+        // Apply min/max updates atomically to avoid invalid states
+        if min_bet.is_some() || max_bet.is_some() {
+            let new_min = min_bet.unwrap_or(config.min_bet);
+            let new_max = max_bet.unwrap_or(config.max_bet);
+            require!(new_min > 0, CustomError::InvalidBetRange);
+            require!(new_max >= new_min, CustomError::InvalidBetRange);
+            config.min_bet = new_min;
+            config.max_bet = new_max;
+        }
+
+        if let Some(mines) = max_mines {
+            require!(mines > 0 && (mines as usize) <= MAX_MINES, CustomError::InvalidNumMines);
+            config.max_mines = mines;
+        }
+
+        if let Some(paused) = is_paused {
+            config.is_paused = paused;
+        }
+
+        // Apply expiry bound updates atomically as well; active games keep their own expiry
+        if min_expiry_secs.is_some() || max_expiry_secs.is_some() {
+            let new_min = min_expiry_secs.unwrap_or(config.min_expiry_secs);
+            let new_max = max_expiry_secs.unwrap_or(config.max_expiry_secs);
+            require!(new_min > 0, CustomError::InvalidExpiry);
+            require!(new_max >= new_min, CustomError::InvalidExpiry);
+            config.min_expiry_secs = new_min;
+            config.max_expiry_secs = new_max;
+        }
+
+        if let Some(grace) = abandon_grace_secs {
+            require!(grace >= 0, CustomError::InvalidExpiry);
+            config.abandon_grace_secs = grace;
+        }
+
+        // Active games keep the cap they reserved at start_game
+        if let Some(cap) = max_payout {
+            require!(cap > 0, CustomError::InvalidAmount);
+            config.max_payout = cap;
+        }
+
+        if let Some(response) = reveal_response_secs {
+            require!(response > 0, CustomError::InvalidExpiry);
+            config.reveal_response_secs = response;
+        }
+
+        msg!(
+            "Settings updated: house={:?}, house_edge_bps={:?}, min_bet={:?}, max_bet={:?}, max_mines={:?}, is_paused={:?}, min_expiry_secs={:?}, max_expiry_secs={:?}, abandon_grace_secs={:?}, max_payout={:?}, reveal_response_secs={:?}",
+            house,
+            house_edge_bps,
+            min_bet,
+            max_bet,
+            max_mines,
+            is_paused,
+            min_expiry_secs,
+            max_expiry_secs,
+            abandon_grace_secs,
+            max_payout,
+            reveal_response_secs
+        );
+        Ok(())
+    }
+
+    /// Creates the shared bankroll that takes every stake and pays every cash-out
+    pub fn initialize_bankroll(ctx: Context<InitializeBankroll>) -> Result<()> {
+        let bankroll = &mut ctx.accounts.bankroll;
+        bankroll.authority = ctx.accounts.authority.key();
+        bankroll.reserved = 0;
+        bankroll.bump = ctx.bumps.bankroll;
+
+        msg!("Mines bankroll initialized for {}", bankroll.authority);
+        Ok(())
+    }
+
+    /// Funds the bankroll
+    pub fn fund_bankroll(ctx: Context<FundBankroll>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidAmount);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.bankroll.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        msg!("Funded mines bankroll with {} lamports", amount);
+        Ok(())
+    }
+
+    /// Withdraws free funds from the bankroll; lamports reserved for active games stay put
+    pub fn withdraw_bankroll(ctx: Context<WithdrawBankroll>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidAmount);
+
+        let bankroll = &ctx.accounts.bankroll;
+        let min_rent_balance = Rent::get()?.minimum_balance(8 + Bankroll::LEN);
+        require!(
+            bankroll
+                .to_account_info()
+                .lamports()
+                .saturating_sub(bankroll.reserved)
+                .saturating_sub(amount)
+                >= min_rent_balance,
+            CustomError::InsufficientBankroll
+        );
+
+        **ctx.accounts.bankroll.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        msg!("Withdrew {} lamports from mines bankroll", amount);
+        Ok(())
+    }
+
+    /// Whitelists an SPL mint (Token or Token-2022) as bet currency with its own bankroll, limits
+    /// and payout cap. Only the bankroll authority can do this. Mints with transfer fees are not supported.
+    pub fn initialize_token_bankroll(
+        ctx: Context<InitializeTokenBankroll>,
+        min_bet: u64,
+        max_bet: u64,
+        max_payout: u64,
+    ) -> Result<()> {
+        require!(min_bet > 0 && max_bet >= min_bet, CustomError::InvalidBetRange);
+        require!(max_payout > 0, CustomError::InvalidAmount);
+        require!(
+            !has_transfer_fee(&ctx.accounts.mint.to_account_info())?,
+            CustomError::TransferFeeMint
+        );
+
+        let token_bankroll = &mut ctx.accounts.token_bankroll;
+        token_bankroll.authority = ctx.accounts.authority.key();
+        token_bankroll.mint = ctx.accounts.mint.key();
+        token_bankroll.vault = ctx.accounts.token_vault.key();
+        token_bankroll.min_bet = min_bet;
+        token_bankroll.max_bet = max_bet;
+        token_bankroll.max_payout = max_payout;
+        token_bankroll.reserved = 0;
+        token_bankroll.total_wagered = 0;
+        token_bankroll.total_paid_out = 0;
+        token_bankroll.bump = ctx.bumps.token_bankroll;
+
+        msg!("Mines token bankroll initialized for mint {}", token_bankroll.mint);
+        Ok(())
+    }
+
+    /// Updates a token bankroll's bet limits and payout cap, in the mint's base units
+    pub fn update_token_bankroll(
+        ctx: Context<UpdateTokenBankroll>,
+        min_bet: Option<u64>,
+        max_bet: Option<u64>,
+        max_payout: Option<u64>,
+    ) -> Result<()> {
+        let token_bankroll = &mut ctx.accounts.token_bankroll;
+
+        // Apply min/max updates atomically to avoid invalid states
+        if min_bet.is_some() || max_bet.is_some() {
+            let new_min = min_bet.unwrap_or(token_bankroll.min_bet);
+            let new_max = max_bet.unwrap_or(token_bankroll.max_bet);
+            require!(new_min > 0, CustomError::InvalidBetRange);
+            require!(new_max >= new_min, CustomError::InvalidBetRange);
+            token_bankroll.min_bet = new_min;
+            token_bankroll.max_bet = new_max;
+        }
+
+        if let Some(cap) = max_payout {
+            require!(cap > 0, CustomError::InvalidAmount);
+            token_bankroll.max_payout = cap;
+        }
+
+        msg!(
+            "Mines token bankroll {} updated: min_bet={:?}, max_bet={:?}, max_payout={:?}",
+            token_bankroll.mint,
+            min_bet,
+            max_bet,
+            max_payout
+        );
+        Ok(())
+    }
+
+    /// Funds a token bankroll
+    pub fn fund_token_bankroll(ctx: Context<FundTokenBankroll>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidAmount);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.funder_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.token_vault.to_account_info(),
+                    authority: ctx.accounts.funder.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        msg!("Funded mines token bankroll {} with {}", ctx.accounts.mint.key(), amount);
+        Ok(())
+    }
+
+    /// Withdraws free tokens from a token bankroll; tokens reserved for active games stay put
+    pub fn withdraw_token_bankroll(ctx: Context<WithdrawTokenBankroll>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidAmount);
+
+        let token_bankroll = &ctx.accounts.token_bankroll;
+        require!(
+            ctx.accounts
+                .token_vault
+                .amount
+                .saturating_sub(token_bankroll.reserved)
+                >= amount,
+            CustomError::InsufficientBankroll
+        );
+
+        let mint = token_bankroll.mint;
+        let seeds: &[&[u8]] = &[b"token_bankroll", mint.as_ref(), &[token_bankroll.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.token_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.authority_token_account.to_account_info(),
+                    authority: token_bankroll.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        msg!("Withdrew {} from mines token bankroll {}", amount, mint);
+        Ok(())
+    }
+
     /// Start a new game:
     /// - initializes `game` account (owned by program)
-    /// - initializes `vault` PDA (owned by program) (holds lamports)
+    /// - moves the stake into the shared bankroll, or the mint's token bankroll if `token` accounts are passed
     /// - stores the commit hash (commitment) instead of on-chain mine positions
+    /// - reserves the payout for clearing every safe tile, capped at the table's max payout,
+    ///   plus the bad-board penalty
+    /// The house co-signs, so the committed board provably comes from the house
+    /// and not from a player who already knows where the mines are.
     pub fn start_game(
         ctx: Context<StartGame>,
         bet_amount: u64,
         num_mines: u8,
-        commitment: [u8; 32], // Merkle root of leaves (one per tile)
+        rows: u8,
+        cols: u8,
+        commitment: [u8; 32], // Merkle root of leaves (one per tile, padded to a power of two)
+        expiry_secs: i64,     // time the player has before either side may time the game out
     ) -> Result<()> {
-        let game = &mut ctx.accounts.game;
+        let config = &ctx.accounts.config;
+        let mint = ctx.accounts.token.mint_key();
+        let player = ctx.accounts.player.key();
+        let (min_bet, max_bet, payout_cap) = if mint == Pubkey::default() {
+            (config.min_bet, config.max_bet, config.max_payout)
+        } else {
+            let leg = ctx.accounts.token.leg(mint, player)?;
+            (leg.bankroll.min_bet, leg.bankroll.max_bet, leg.bankroll.max_payout)
+        };
 
         // basic validations
+        require!(!config.is_paused, CustomError::GamePaused);
         require!(bet_amount > 0, CustomError::InvalidBetAmount);
+        require!(bet_amount >= min_bet, CustomError::BetTooLow);
+        require!(bet_amount <= max_bet, CustomError::BetTooHigh);
+        // A timed-out game hands the stake back, so the stake alone must fit under the cap
+        require!(bet_amount <= payout_cap, CustomError::PayoutExceedsCap);
+        require!(
+            (MIN_BOARD_SIDE..=MAX_BOARD_SIDE).contains(&rows)
+                && (MIN_BOARD_SIDE..=MAX_BOARD_SIDE).contains(&cols),
+            CustomError::InvalidBoardSize
+        );
         require!(
-            num_mines > 0 && (num_mines as usize) <= MAX_MINES && (num_mines as usize) < MAX_BOARD_SIZE,
+            (config.min_expiry_secs..=config.max_expiry_secs).contains(&expiry_secs),
+            CustomError::InvalidExpiry
+        );
+        let tiles = rows * cols;
+        require!(
+            num_mines > 0 && num_mines <= config.max_mines && num_mines < tiles,
             CustomError::InvalidNumMines
         );
 
+        // The player's own limits; stakes count as lost in their currency until paid back
+        let now = Clock::get()?.unix_timestamp;
+        let player_limits = &mut ctx.accounts.player_limits;
+        player_limits.init_if_new(player, ctx.bumps.player_limits);
+        player_limits
+            .limits
+            .record_play(now, mint, bet_amount, bet_amount)
+            .map_err(CustomError::from)?;
+
+        // The stake lands in the bankroll, which must then cover the best possible cash-out.
+        // Big boards can pay more than any bankroll holds, so the cash-out is capped.
+        let house_edge_bps = config.house_edge_bps;
+        let max_payout = payout_for(bet_amount, tiles, num_mines, tiles - num_mines, house_edge_bps, payout_cap)?
+            .checked_add(board_penalty(bet_amount))
+            .ok_or(CustomError::Overflow)?;
+        if mint == Pubkey::default() {
+            let bankroll = &mut ctx.accounts.bankroll;
+            let min_rent_balance = Rent::get()?.minimum_balance(8 + Bankroll::LEN);
+            require!(
+                bankroll
+                    .to_account_info()
+                    .lamports()
+                    .saturating_add(bet_amount)
+                    .saturating_sub(bankroll.reserved)
+                    .saturating_sub(max_payout)
+                    >= min_rent_balance,
+                CustomError::InsufficientBankroll
+            );
+            bankroll.reserved = bankroll
+                .reserved
+                .checked_add(max_payout)
+                .ok_or(CustomError::Overflow)?;
+
+            // Transfer bet lamports from player into the bankroll
+            let cpi_accounts = system_program::Transfer {
+                from: ctx.accounts.player.to_account_info(),
+                to: ctx.accounts.bankroll.to_account_info(),
+            };
+            system_program::transfer(
+                CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts),
+                bet_amount,
+            )?;
+        } else {
+            let accounts = &mut *ctx.accounts;
+            let leg = accounts.token.leg(mint, player)?;
+            require!(
+                leg.vault
+                    .amount
+                    .saturating_add(bet_amount)
+                    .saturating_sub(leg.bankroll.reserved)
+                    >= max_payout,
+                CustomError::InsufficientBankroll
+            );
+            leg.bankroll.reserved = leg
+                .bankroll
+                .reserved
+                .checked_add(max_payout)
+                .ok_or(CustomError::Overflow)?;
+            leg.bankroll.total_wagered = leg.bankroll.total_wagered.saturating_add(bet_amount);
+
+            // Transfer bet tokens from player into the token bankroll
+            leg.deposit(&accounts.player, bet_amount)?;
+        }
+
         // fill game state
-        game.player = ctx.accounts.player.key();
+        let game = &mut ctx.accounts.game;
+        game.version = GAME_VERSION;
+        game.player = player;
         game.house = ctx.accounts.house.key();
         game.bet_amount = bet_amount;
         game.num_mines = num_mines;
-        game.revealed_tiles = [false; MAX_BOARD_SIZE];
-        game.mine_positions = [false; MAX_BOARD_SIZE]; // unused with Merkle per-tile proofs; kept for compatibility
+        game.rows = rows;
+        game.cols = cols;
+        game.revealed = 0;
         game.is_active = true;
         game.lost = false;
         game.revealed_count = 0;
         game.commitment = commitment;
         game.state = STATE_COMMITTED;
-        let now = Clock::get()?.unix_timestamp;
         game.started_at = now;
-        game.expiry_ts = now.saturating_add(DEFAULT_EXPIRY_SECS);
+        game.expiry_ts = now.saturating_add(expiry_secs);
+        game.house_edge_bps = house_edge_bps;
+        game.reserved = max_payout;
+        game.mint = mint;
+        game.requested = 0;
+        game.requested_at = 0;
+        game.legacy_board = false;
+
+        emit!(GameStarted {
+            game: game.key(),
+            player: game.player,
+            house: game.house,
+            mint,
+            bet_amount,
+            num_mines,
+            rows,
+            cols,
+            commitment,
+            expiry_ts: game.expiry_ts,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// Converts a game started by the v1 program (5x5, `revealed_tiles` as a bool array, stake held
+    /// in a per-game `vault` PDA) to the current layout. The account is rewritten in place, so its
+    /// address, commitment and expiry are unchanged. The stake moves from the vault into the
+    /// bankroll, which reserves the game's max payout as if it had started there; the vault is
+    /// closed and its rent goes back to the player. A lost game waits for the house to close it
+    /// in `finalize_board`, as `collect_house` did. Anyone may call it; the current layout is
+    /// larger, and `payer` tops up the rent it needs.
+    pub fn migrate_game(ctx: Context<MigrateGame>) -> Result<()> {
+        let info = ctx.accounts.game.to_account_info();
+        let v1 = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() == 8 + MinesGameV1::LEN && data[..8] == GAME_V1_DISCRIMINATOR,
+                CustomError::NotLegacyGame
+            );
+            MinesGameV1::deserialize(&mut &data[8..])?
+        };
+        require_keys_eq!(v1.player, ctx.accounts.player.key(), CustomError::NotLegacyGame);
+        require!(v1.is_active || v1.lost, CustomError::GameNotActive);
 
-        // set vault bump in its data
-        let vault_bump = ctx.bumps.vault;
-        ctx.accounts.vault.bump = vault_bump;
+        // Stake from the vault into the bankroll; Anchor closes the vault to the player
+        let vault = ctx.accounts.vault.to_account_info();
+        require!(vault.lamports() >= v1.bet_amount, CustomError::InsufficientVaultFunds);
+        **vault.try_borrow_mut_lamports()? -= v1.bet_amount;
+        **ctx.accounts.bankroll.to_account_info().try_borrow_mut_lamports()? += v1.bet_amount;
 
-        // Transfer bet lamports from player into vault PDA
-        // player must be mutable (payer). Vault already created by Anchor `init`.
-        let cpi_accounts = system_program::Transfer {
-            from: ctx.accounts.player.to_account_info(),
-            to: ctx.accounts.vault.to_account_info(),
+        let config = &ctx.accounts.config;
+        let now = Clock::get()?.unix_timestamp;
+        let tiles = LEGACY_BOARD_SIDE * LEGACY_BOARD_SIDE;
+        let (state, expiry_ts, reserved) = if v1.is_active {
+            // v1 board commitments carry no board seed, so there is no penalty to hold back
+            let max_payout = payout_for(
+                v1.bet_amount,
+                tiles,
+                v1.num_mines,
+                tiles - v1.num_mines,
+                config.house_edge_bps,
+                config.max_payout,
+            )?
+            .max(v1.bet_amount);
+            let bankroll = &mut ctx.accounts.bankroll;
+            let min_rent_balance = Rent::get()?.minimum_balance(8 + Bankroll::LEN);
+            require!(
+                bankroll
+                    .to_account_info()
+                    .lamports()
+                    .saturating_sub(bankroll.reserved)
+                    .saturating_sub(max_payout)
+                    >= min_rent_balance,
+                CustomError::InsufficientBankroll
+            );
+            bankroll.reserved = bankroll
+                .reserved
+                .checked_add(max_payout)
+                .ok_or(CustomError::Overflow)?;
+            (STATE_COMMITTED, v1.expiry_ts, max_payout)
+        } else {
+            (STATE_AWAITING_BOARD, now.saturating_add(BOARD_REVEAL_SECS), 0)
+        };
+
+        let revealed = v1
+            .revealed_tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, revealed)| **revealed)
+            .fold(0u64, |bits, (i, _)| bits | 1 << i);
+        let game = MinesGame {
+            version: GAME_VERSION,
+            player: v1.player,
+            house: v1.house,
+            bet_amount: v1.bet_amount,
+            num_mines: v1.num_mines,
+            revealed,
+            is_active: v1.is_active,
+            lost: v1.lost,
+            revealed_count: v1.revealed_count,
+            commitment: v1.commitment,
+            state,
+            started_at: v1.started_at,
+            expiry_ts,
+            house_edge_bps: config.house_edge_bps,
+            reserved,
+            rows: LEGACY_BOARD_SIDE,
+            cols: LEGACY_BOARD_SIDE,
+            mint: Pubkey::default(), // v1 games were always staked in SOL
+            requested: 0,
+            requested_at: 0,
+            legacy_board: true,
         };
-        system_program::transfer(
-            CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts),
-            bet_amount,
-        )?;
 
+        let new_len = 8 + MinesGame::LEN;
+        let top_up = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(info.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        // solana-account-info 2.2 (pinned through Anchor 0.31) has no `resize` yet; this is the same call
+        info.realloc(new_len, false)?;
+        game.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        msg!("Migrated game {} to v{}", info.key(), GAME_VERSION);
         Ok(())
     }
 
-    /// Deprecated: global reveal is removed in favor of per-tile Merkle proofs.
-    /// Retained to avoid IDL drift for older clients; now always errors.
-    pub fn set_mine_positions(_ctx: Context<SetMinePositions>, _nonce: [u8; 32], _bytes: [u8; MAX_BOARD_SIZE]) -> Result<()> {
-        err!(CustomError::DeprecatedInstruction)
+    /// Player asks for the openings of one or more tiles (bit i set = tile i). The house serves an
+    /// opening only for tiles requested here, so it can later prove what it served: a requested tile
+    /// stays pending until either side opens it on-chain, and the game cannot be cashed out meanwhile.
+    pub fn request_tiles(ctx: Context<RevealTile>, tiles: u64) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        require!(game.is_active, CustomError::GameNotActive);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < game.expiry_ts, CustomError::GameExpired);
+        let board_mask = u64::MAX >> (64 - game.tiles() as u32);
+        require!(tiles != 0 && tiles & !board_mask == 0, CustomError::InvalidTileIndex);
+        require!(tiles & game.revealed == 0, CustomError::TileAlreadyRevealed);
+
+        // The house's response window runs from the oldest unanswered request
+        if game.requested == 0 {
+            game.requested_at = now;
+        }
+        game.requested |= tiles;
+
+        emit!(TilesRequested {
+            game: game.key(),
+            player: game.player,
+            tiles,
+            timestamp: now,
+        });
+        Ok(())
     }
 
     /// Player reveals a tile by providing a Merkle proof for that tile.
     /// Leaf hash = hash("leaf" || tile_index || is_mine || leaf_nonce)
-    /// `proof` is fixed-depth MERKLE_DEPTH; `path_bits` indicates left/right at each depth (LSB = level 0).
+    /// leaf_nonce = hash("nonce" || board_seed || tile_index), so the house can open the whole board later
+    /// `proof` has one sibling per level of the game's Merkle depth; `path_bits` indicates left/right
+    /// at each depth (LSB = level 0).
     pub fn reveal_tile(
         ctx: Context<RevealTile>,
         tile_index: u8,
         is_mine: u8, // 0 or 1
         leaf_nonce: [u8; 32],
-        proof: [[u8; 32]; MERKLE_DEPTH],
+        proof: Vec<[u8; 32]>,
         path_bits: u32,
     ) -> Result<()> {
         let game = &mut ctx.accounts.game;
         require!(game.is_active, CustomError::GameNotActive);
         require!(game.state == STATE_COMMITTED, CustomError::GameNotRevealed);
-        require!((tile_index as usize) < MAX_BOARD_SIZE, CustomError::InvalidTileIndex);
-        require!(!game.revealed_tiles[tile_index as usize], CustomError::TileAlreadyRevealed);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < game.expiry_ts, CustomError::GameExpired);
+        require!(tile_index < game.tiles(), CustomError::InvalidTileIndex);
+        require!(game.revealed & (1 << tile_index) == 0, CustomError::TileAlreadyRevealed);
+        require!(proof.len() == game.merkle_depth(), CustomError::InvalidProofLength);
 
         // Verify Merkle proof against committed root
         let leaf = compute_leaf_hash(tile_index, is_mine, &leaf_nonce);
         let root = compute_merkle_root(leaf, &proof, path_bits);
         require!(root == game.commitment, CustomError::InvalidCommitment);
 
-        game.revealed_tiles[tile_index as usize] = true;
-        game.revealed_count = game.revealed_count.saturating_add(1);
+        record_reveal(game, tile_index, is_mine != 0, now);
+        Ok(())
+    }
 
-        if is_mine != 0 {
-            // player hit a mine => game lost
-            game.is_active = false;
-            game.lost = true;
-            game.state = STATE_FINISHED;
-        }
+    /// Player reveals several tiles at once (auto-pick), in the order given.
+    /// All leaves are checked against the committed root with one multiproof: `nodes` holds only
+    /// the sibling hashes that cannot be computed from the opened leaves, level by level from the
+    /// leaves up and left to right within a level, so shared nodes are sent once.
+    /// Stops at the first mine; tiles after it stay hidden.
+    pub fn reveal_tiles(ctx: Context<RevealTile>, tiles: Vec<TileOpening>, nodes: Vec<[u8; 32]>) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < game.expiry_ts, CustomError::GameExpired);
+        open_tiles(game, &tiles, &nodes, now)?;
+        Ok(())
+    }
 
+    /// House opens tiles the player requested but did not reveal, with the same multiproof as
+    /// `reveal_tiles`. This is how a player who was served a mine and withholds it forfeits the
+    /// game; it stays open to the house after expiry, until the player aborts.
+    pub fn house_reveal_tiles(
+        ctx: Context<HouseRevealTiles>,
+        tiles: Vec<TileOpening>,
+        nodes: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        let requested = game.requested;
+        let picked = open_tiles(game, &tiles, &nodes, Clock::get()?.unix_timestamp)?;
+        require!(picked & !requested == 0, CustomError::TileNotRequested);
         Ok(())
     }
 
     /// Player cashes out (only allowed when game is active and not lost).
-    /// This will close both the vault PDA and the game account and send all vault lamports to player.
+    /// The payout follows the fair odds of surviving `revealed_count` picks less the house edge,
+    /// up to the payout cap the game reserved at start, and comes out of the bankroll right away. The game stays open, with only the penalty
+    /// still reserved, until the house opens the board in `finalize_board`.
     pub fn cash_out(ctx: Context<CashOut>) -> Result<()> {
         let game = &mut ctx.accounts.game;
         require!(game.is_active, CustomError::GameNotActive);
         require!(!game.lost, CustomError::PlayerLost);
         require!(game.revealed_count > 0, CustomError::NothingToCashOut);
+        require!(game.requested == 0, CustomError::RevealPending);
 
-        game.is_active = false;
-        game.state = STATE_FINISHED;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < game.expiry_ts, CustomError::GameExpired);
+        let payout = payout_for(
+            game.bet_amount,
+            game.tiles(),
+            game.num_mines,
+            game.revealed_count,
+            game.house_edge_bps,
+            game.payout_cap(),
+        )?;
+        pay_out_game(
+            game,
+            &mut ctx.accounts.bankroll,
+            &mut ctx.accounts.token,
+            &mut ctx.accounts.player_limits,
+            &ctx.accounts.player.to_account_info(),
+            payout,
+            now,
+        )?;
+
+        emit!(CashedOut {
+            game: game.key(),
+            player: game.player,
+            mint: game.mint,
+            bet_amount: game.bet_amount,
+            payout,
+            revealed_count: game.revealed_count,
+            timestamp: now,
+        });
 
-        // Anchor will automatically close `vault` to `player` (per close attribute),
-        // and close `game` to `player`.
         Ok(())
     }
 
-    /// House collects funds when player lost (sweeps the vault).
-    /// `house` must be the same public key saved at start_game and must sign to collect.
-    pub fn collect_house(ctx: Context<CollectHouse>) -> Result<()> {
+    /// House settles a game the player walked away from, once `abandon_grace_secs` have passed
+    /// after expiry. The player gets their current cash-out value (the stake if nothing was
+    /// revealed), and the game then waits for `finalize_board` like any cash-out.
+    /// Requested tiles must be opened with `house_reveal_tiles` first.
+    pub fn settle_abandoned(ctx: Context<SettleAbandoned>) -> Result<()> {
         let game = &mut ctx.accounts.game;
-        require!(game.lost, CustomError::GameNotLost);
+        require!(game.is_active, CustomError::GameNotActive);
+        require!(game.requested == 0, CustomError::RevealPending);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= game
+                .expiry_ts
+                .saturating_add(ctx.accounts.config.abandon_grace_secs),
+            CustomError::NotExpired
+        );
 
-        // Anchor will close `vault` and `game` to `house` (close = house), returning lamports to house.
+        let payout = if game.revealed_count == 0 {
+            game.bet_amount
+        } else {
+            payout_for(
+                game.bet_amount,
+                game.tiles(),
+                game.num_mines,
+                game.revealed_count,
+                game.house_edge_bps,
+                game.payout_cap(),
+            )?
+        };
+        pay_out_game(
+            game,
+            &mut ctx.accounts.bankroll,
+            &mut ctx.accounts.token,
+            &mut ctx.accounts.player_limits,
+            &ctx.accounts.player.to_account_info(),
+            payout,
+            now,
+        )?;
+
+        emit!(AbandonedGameSettled {
+            game: game.key(),
+            player: game.player,
+            house: game.house,
+            mint: game.mint,
+            payout,
+            revealed_count: game.revealed_count,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+
+    /// House opens every leaf once the game is over by revealing the board seed and the mine bitmap
+    /// (bit i set = tile i is a mine). The program rebuilds the Merkle root and checks that the board
+    /// holds exactly `num_mines` mines and agrees with the tiles the player revealed.
+    /// A board that fails the check refunds the player's stake (if lost) plus the penalty.
+    /// Games migrated from v1 have no board seed to open, so the house just closes them.
+    /// The game account is closed to the player, who paid its rent.
+    pub fn finalize_board(ctx: Context<FinalizeBoard>, board_seed: [u8; 32], mines: u64) -> Result<()> {
+        let game = &mut ctx.accounts.game;
+        require!(game.state == STATE_AWAITING_BOARD, CustomError::GameNotOver);
+
+        let board_mask = u64::MAX >> (64 - game.tiles() as u32);
+        // A legacy board has nothing to check against, so it skips every check
+        let valid = if game.legacy_board {
+            true
+        } else {
+            mines & !board_mask == 0
+                && mines.count_ones() == game.num_mines as u32
+                && (mines & game.revealed).count_ones() == game.lost as u32
+                && compute_board_root(&board_seed, mines, game.merkle_depth()) == game.commitment
+        };
+
+        game.state = STATE_FINISHED;
+
+        let now = Clock::get()?.unix_timestamp;
+        let refund = if valid {
+            if game.lost {
+                emit!(HouseCollected {
+                    game: game.key(),
+                    player: game.player,
+                    house: game.house,
+                    amount: game.bet_amount,
+                    revealed_count: game.revealed_count,
+                    timestamp: now,
+                });
+            }
+            0
+        } else {
+            board_refund(game)
+        };
+        release_and_pay(
+            game,
+            &mut ctx.accounts.bankroll,
+            &mut ctx.accounts.token,
+            &mut ctx.accounts.player_limits,
+            &ctx.accounts.player.to_account_info(),
+            game.reserved,
+            refund,
+            0,
+        )?;
+
+        emit!(BoardFinalized {
+            game: game.key(),
+            player: game.player,
+            house: game.house,
+            mint: game.mint,
+            valid,
+            mines,
+            refund,
+            timestamp: now,
+        });
+
+        // Anchor will close `game` to `player` (close = player), returning rent.
         Ok(())
     }
 
-    /// If the house never provides proofs and the game stalls past expiry, the player can abort and refund.
+    /// Player reclaims funds from a game the house stopped serving:
+    /// - still active, with a requested tile left unopened for `reveal_response_secs`, or
+    ///   expired and never settled by the house: the stake comes back
+    /// - over but the house never opened the board: the stake (if lost) plus the penalty
     pub fn abort_refund(ctx: Context<AbortRefund>) -> Result<()> {
         let game = &mut ctx.accounts.game;
-        require!(game.is_active, CustomError::GameNotActive);
-        require!(!game.lost, CustomError::PlayerLost);
+        let config = &ctx.accounts.config;
+        require!(
+            game.is_active || game.state == STATE_AWAITING_BOARD,
+            CustomError::GameNotActive
+        );
         let now = Clock::get()?.unix_timestamp;
-        require!(now >= game.expiry_ts, CustomError::NotExpired);
+        let deadline = if !game.is_active {
+            game.expiry_ts
+        } else if game.requested != 0 {
+            game.requested_at.saturating_add(config.reveal_response_secs)
+        } else {
+            // Nothing withheld; the house had its own window to settle the abandoned game
+            game.expiry_ts
+                .saturating_add(config.abandon_grace_secs)
+                .saturating_add(config.reveal_response_secs)
+        };
+        require!(now >= deadline, CustomError::NotExpired);
 
+        // An active game's stake comes back unplayed, so it no longer counts as wagered
+        let (refund, stake_returned) = if game.is_active {
+            (game.bet_amount, game.bet_amount)
+        } else {
+            (board_refund(game), 0)
+        };
         game.is_active = false;
         game.state = STATE_FINISHED;
+
+        release_and_pay(
+            game,
+            &mut ctx.accounts.bankroll,
+            &mut ctx.accounts.token,
+            &mut ctx.accounts.player_limits,
+            &ctx.accounts.player.to_account_info(),
+            game.reserved,
+            refund,
+            stake_returned,
+        )?;
+
+        emit!(GameAborted {
+            game: game.key(),
+            player: game.player,
+            mint: game.mint,
+            refund,
+            revealed_count: game.revealed_count,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Sets the player's own limits. Tightening takes effect immediately;
+    /// loosening any limit only after LIMIT_LOOSENING_DELAY.
+    pub fn set_limits(ctx: Context<SetLimits>, settings: LimitSettings) -> Result<()> {
+        let player_limits = &mut ctx.accounts.player_limits;
+        player_limits.init_if_new(ctx.accounts.player.key(), ctx.bumps.player_limits);
+        player_limits
+            .limits
+            .request(Clock::get()?.unix_timestamp, settings)
+            .map_err(CustomError::from)?;
+
+        msg!("Limits updated; loosening pending until {}", player_limits.limits.pending_at);
         Ok(())
     }
 }
 
 /*** Accounts & structs ***/
 
+/// One opened leaf in `reveal_tiles`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TileOpening {
+    pub tile_index: u8,
+    pub is_mine: u8, // 0 or 1
+    pub leaf_nonce: [u8; 32],
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MinesConfig::LEN,
+        seeds = [b"mines_config"],
+        bump
+    )]
+    pub config: Account<'info, MinesConfig>,
+
+    #[account(seeds = [b"bankroll"], bump = bankroll.bump, has_one = authority)]
+    pub bankroll: Account<'info, Bankroll>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSettings<'info> {
+    #[account(mut, seeds = [b"mines_config"], bump = config.bump, has_one = authority)]
+    pub config: Account<'info, MinesConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeBankroll<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Bankroll::LEN,
+        seeds = [b"bankroll"],
+        bump
+    )]
+    pub bankroll: Account<'info, Bankroll>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundBankroll<'info> {
+    #[account(mut, seeds = [b"bankroll"], bump = bankroll.bump)]
+    pub bankroll: Account<'info, Bankroll>,
+
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBankroll<'info> {
+    #[account(mut, seeds = [b"bankroll"], bump = bankroll.bump, has_one = authority)]
+    pub bankroll: Account<'info, Bankroll>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct StartGame<'info> {
     #[account(init, payer = player, space = 8 + MinesGame::LEN)]
     pub game: Account<'info, MinesGame>,
 
-    // Vault PDA to store lamports; small account that stores the bump.
+    #[account(seeds = [b"mines_config"], bump = config.bump)]
+    pub config: Account<'info, MinesConfig>,
+
+    // Shared bankroll; holds every stake and reserves every game's max payout
+    #[account(mut, seeds = [b"bankroll"], bump = bankroll.bump)]
+    pub bankroll: Account<'info, Bankroll>,
+
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    /// House must sign off on the commitment and be the house named in the config
+    #[account(constraint = house.key() == config.house @ CustomError::InvalidHouse)]
+    pub house: Signer<'info>,
+
+    // The player's own limits, created empty (no limits) on their first game
     #[account(
-        init,
+        init_if_needed,
         payer = player,
-        seeds = [b"vault", game.key().as_ref()],
-        bump,
-        space = 8 + Vault::LEN
+        space = 8 + PlayerLimits::LEN,
+        seeds = [b"limits", player.key().as_ref()],
+        bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub player_limits: Account<'info, PlayerLimits>,
+
+    pub token: TokenAccounts<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetLimits<'info> {
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + PlayerLimits::LEN,
+        seeds = [b"limits", player.key().as_ref()],
+        bump
+    )]
+    pub player_limits: Account<'info, PlayerLimits>,
 
     #[account(mut)]
     pub player: Signer<'info>,
 
-    /// CHECK: house is just a public key / recipient (not owned by program here)
-    pub house: UncheckedAccount<'info>,
-
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SetMinePositions<'info> {
-    #[account(mut, has_one = house)]
-    pub game: Account<'info, MinesGame>,
+pub struct MigrateGame<'info> {
+    /// CHECK: v1 data no longer deserializes as MinesGame; owner is checked here and the
+    /// discriminator and length in migrate_game
+    #[account(mut, owner = crate::ID)]
+    pub game: UncheckedAccount<'info>,
 
-    /// House must sign to reveal positions (must match game.house).
-    pub house: Signer<'info>,
+    // The v1 game's stake; drained into the bankroll and closed to the player
+    #[account(mut, seeds = [b"vault", game.key().as_ref()], bump = vault.bump, close = player)]
+    pub vault: Account<'info, Vault>,
+
+    #[account(seeds = [b"mines_config"], bump = config.bump)]
+    pub config: Account<'info, MinesConfig>,
+
+    // Shared bankroll; takes the stake and reserves the game's max payout
+    #[account(mut, seeds = [b"bankroll"], bump = bankroll.bump)]
+    pub bankroll: Account<'info, Bankroll>,
+
+    /// CHECK: only receives the vault's rent; must be the game's player
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+
+    /// Pays the extra rent for the larger layout
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -195,38 +1066,87 @@ pub struct RevealTile<'info> {
     pub player: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct HouseRevealTiles<'info> {
+    #[account(mut, has_one = house)]
+    pub game: Account<'info, MinesGame>,
+
+    /// House must sign to open tiles on the player's behalf
+    pub house: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct CashOut<'info> {
-    // Close the game acct to player when successful cash_out.
-    #[account(mut, has_one = player, close = player)]
+    // Stays open until the house opens the board
+    #[account(mut, has_one = player)]
     pub game: Account<'info, MinesGame>,
 
-    // Close the vault PDA and send lamports to player (close = player).
-    #[account(mut, seeds = [b"vault", game.key().as_ref()], bump, close = player)]
-    pub vault: Account<'info, Vault>,
+    // Shared bankroll; holds every stake and reserves every game's max payout
+    #[account(mut, seeds = [b"bankroll"], bump = bankroll.bump)]
+    pub bankroll: Account<'info, Bankroll>,
 
-    /// Player must be writable because lamports will arrive here and account will be recipient of closes.
+    /// Player must be writable because the payout arrives here.
     #[account(mut)]
     pub player: Signer<'info>,
 
+    // The player's limits, to credit what is paid back; placing the game created them
+    #[account(mut, seeds = [b"limits", player.key().as_ref()], bump = player_limits.bump)]
+    pub player_limits: Account<'info, PlayerLimits>,
+
+    pub token: TokenAccounts<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CollectHouse<'info> {
-    // Close the game acct to house
-    #[account(mut, has_one = house, close = house)]
+pub struct SettleAbandoned<'info> {
+    // Stays open until the house opens the board
+    #[account(mut, has_one = player, has_one = house)]
     pub game: Account<'info, MinesGame>,
 
-    // Close the vault PDA and send lamports to house
-    #[account(mut, seeds = [b"vault", game.key().as_ref()], bump, close = house)]
-    pub vault: Account<'info, Vault>,
+    #[account(seeds = [b"mines_config"], bump = config.bump)]
+    pub config: Account<'info, MinesConfig>,
 
-    /// House must sign to collect lost funds
+    // Shared bankroll; holds every stake and reserves every game's max payout
+    #[account(mut, seeds = [b"bankroll"], bump = bankroll.bump)]
+    pub bankroll: Account<'info, Bankroll>,
+
+    /// CHECK: only receives the payout; checked against game.player via has_one
     #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+
+    /// House must sign to settle the game
     pub house: Signer<'info>,
 
-    pub system_program: Program<'info, System>,
+    // The player's limits, to credit what is paid back; placing the game created them
+    #[account(mut, seeds = [b"limits", player.key().as_ref()], bump = player_limits.bump)]
+    pub player_limits: Account<'info, PlayerLimits>,
+
+    pub token: TokenAccounts<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeBoard<'info> {
+    // Close the game acct to player (who paid its rent)
+    #[account(mut, has_one = player, has_one = house, close = player)]
+    pub game: Account<'info, MinesGame>,
+
+    // Shared bankroll; holds every stake and reserves every game's max payout
+    #[account(mut, seeds = [b"bankroll"], bump = bankroll.bump)]
+    pub bankroll: Account<'info, Bankroll>,
+
+    /// CHECK: only receives rent and any refund; checked against game.player via has_one
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+
+    /// House must sign to open the board
+    pub house: Signer<'info>,
+
+    // The player's limits, to credit what is paid back; placing the game created them
+    #[account(mut, seeds = [b"limits", player.key().as_ref()], bump = player_limits.bump)]
+    pub player_limits: Account<'info, PlayerLimits>,
+
+    pub token: TokenAccounts<'info>,
 }
 
 #[derive(Accounts)]
@@ -235,44 +1155,390 @@ pub struct AbortRefund<'info> {
     #[account(mut, has_one = player, close = player)]
     pub game: Account<'info, MinesGame>,
 
-    // Close the vault PDA and send lamports to player (close = player)
-    #[account(mut, seeds = [b"vault", game.key().as_ref()], bump, close = player)]
-    pub vault: Account<'info, Vault>,
+    #[account(seeds = [b"mines_config"], bump = config.bump)]
+    pub config: Account<'info, MinesConfig>,
+
+    // Shared bankroll; holds every stake and reserves every game's max payout
+    #[account(mut, seeds = [b"bankroll"], bump = bankroll.bump)]
+    pub bankroll: Account<'info, Bankroll>,
 
     #[account(mut)]
     pub player: Signer<'info>,
 
+    // The player's limits, to credit what is paid back; placing the game created them
+    #[account(mut, seeds = [b"limits", player.key().as_ref()], bump = player_limits.bump)]
+    pub player_limits: Account<'info, PlayerLimits>,
+
+    pub token: TokenAccounts<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for a game staked in an SPL mint; all omitted for SOL games.
+/// They are checked against each other in `leg`, since any of them may be missing.
+#[derive(Accounts)]
+pub struct TokenAccounts<'info> {
+    #[account(mut)]
+    pub token_bankroll: Option<Account<'info, TokenBankroll>>,
+
+    #[account(mut)]
+    pub bankroll_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub player_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTokenBankroll<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenBankroll::LEN,
+        seeds = [b"token_bankroll", mint.key().as_ref()],
+        bump
+    )]
+    pub token_bankroll: Account<'info, TokenBankroll>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"token_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_bankroll,
+        token::token_program = token_program
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    // Only the SOL bankroll authority can whitelist a mint
+    #[account(seeds = [b"bankroll"], bump = bankroll.bump, has_one = authority)]
+    pub bankroll: Account<'info, Bankroll>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateTokenBankroll<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_bankroll", token_bankroll.mint.as_ref()],
+        bump = token_bankroll.bump,
+        has_one = authority
+    )]
+    pub token_bankroll: Account<'info, TokenBankroll>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundTokenBankroll<'info> {
+    #[account(
+        seeds = [b"token_bankroll", mint.key().as_ref()],
+        bump = token_bankroll.bump,
+        has_one = mint
+    )]
+    pub token_bankroll: Account<'info, TokenBankroll>,
+
+    #[account(mut, address = token_bankroll.vault)]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub funder: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokenBankroll<'info> {
+    #[account(
+        seeds = [b"token_bankroll", mint.key().as_ref()],
+        bump = token_bankroll.bump,
+        has_one = mint,
+        has_one = authority
+    )]
+    pub token_bankroll: Account<'info, TokenBankroll>,
+
+    #[account(mut, address = token_bankroll.vault)]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub authority: Signer<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
 #[account]
-pub struct Vault {
-    pub bump: u8,
+pub struct MinesConfig {
+    pub authority: Pubkey,         // 32 (may update settings)
+    pub house: Pubkey,             // 32 (only house accepted by start_game)
+    pub house_edge_bps: u16,       // 2
+    pub min_bet: u64,              // 8
+    pub max_bet: u64,              // 8
+    pub max_mines: u8,             // 1
+    pub is_paused: bool,           // 1
+    pub bump: u8,                  // 1
+    pub min_expiry_secs: i64,      // 8 (bounds for the per-game expiry chosen at start_game)
+    pub max_expiry_secs: i64,      // 8
+    pub abandon_grace_secs: i64,   // 8 (extra wait before the house may settle an expired game)
+    pub max_payout: u64,           // 8 (most a single SOL game may pay out)
+    pub reveal_response_secs: i64, // 8 (time the house has to open a requested tile)
 }
-impl Vault {
-    pub const LEN: usize = 1;
+impl MinesConfig {
+    pub const LEN: usize = 32 + 32 + 2 + 8 + 8 + 1 + 1 + 1 + 8 + 8 + 8 + 8 + 8;
 }
 
 #[account]
+pub struct Bankroll {
+    pub authority: Pubkey, // 32 (may withdraw free funds)
+    pub reserved: u64,     // 8 (max payouts owed to active games)
+    pub bump: u8,          // 1
+}
+impl Bankroll {
+    pub const LEN: usize = 32 + 8 + 1;
+}
+
+/// Bankroll for one whitelisted SPL mint; amounts are in the mint's base units
+#[account]
+pub struct TokenBankroll {
+    pub authority: Pubkey,   // 32 (may update limits and withdraw free funds)
+    pub mint: Pubkey,        // 32
+    pub vault: Pubkey,       // 32 (token account owned by this PDA)
+    pub min_bet: u64,        // 8
+    pub max_bet: u64,        // 8
+    pub reserved: u64,       // 8 (max payouts owed to active games)
+    pub total_wagered: u64,  // 8
+    pub total_paid_out: u64, // 8
+    pub bump: u8,            // 1
+    pub max_payout: u64,     // 8 (most a single game may pay out)
+}
+impl TokenBankroll {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 8;
+}
+
+// v2 gets its own discriminator so v1 accounts fail to load until `migrate_game` runs
+#[account(discriminator = b"minesgv2")]
 pub struct MinesGame {
+    pub version: u8,                          // 1 (GAME_VERSION)
     pub player: Pubkey,                       // 32
     pub house: Pubkey,                        // 32 (who collects on loss)
     pub bet_amount: u64,                      // 8
     pub num_mines: u8,                        // 1
-    pub mine_positions: [bool; MAX_BOARD_SIZE], // 25 (set only after reveal)
-    pub revealed_tiles: [bool; MAX_BOARD_SIZE], // 25
+    pub revealed: u64,                        // 8 (bit i set = tile i revealed)
     pub is_active: bool,                      // 1
     pub lost: bool,                           // 1
     pub revealed_count: u8,                   // 1
     pub commitment: [u8; 32],                 // 32 (commitment hash)
-    pub state: u8,                            // 1 (STATE_COMMITTED/STATE_FINISHED)
+    pub state: u8,                            // 1 (STATE_COMMITTED/STATE_AWAITING_BOARD/STATE_FINISHED)
     pub started_at: i64,                      // 8
-    pub expiry_ts: i64,                       // 8
+    pub expiry_ts: i64,                       // 8 (play deadline, then board deadline once over)
+    pub house_edge_bps: u16,                  // 2 (edge locked in at start_game)
+    pub reserved: u64,                        // 8 (max payout + penalty held back in the bankroll)
+    pub rows: u8,                             // 1
+    pub cols: u8,                             // 1
+    pub mint: Pubkey,                         // 32 (bet currency; default is SOL)
+    pub requested: u64,                       // 8 (bit i set = tile i requested, not yet opened)
+    pub requested_at: i64,                    // 8 (oldest unanswered request)
+    pub legacy_board: bool,                   // 1 (migrated from v1; the board has no seed to open)
 }
 
 impl MinesGame {
-    // Total fields size (without discriminator): 175 bytes given MAX_BOARD_SIZE=25
-    pub const LEN: usize = 32 + 32 + 8 + 1 + MAX_BOARD_SIZE + MAX_BOARD_SIZE + 1 + 1 + 1 + 32 + 1 + 8 + 8;
+    // Total fields size (without discriminator): 195 bytes
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 1 + 8 + 1 + 1 + 1 + 32 + 1 + 8 + 8 + 2 + 8 + 1 + 1 + 32 + 8 + 8 + 1;
+
+    pub fn tiles(&self) -> u8 {
+        self.rows * self.cols
+    }
+
+    /// Most this game may pay on a cash-out: its reservation less the bad-board penalty
+    pub fn payout_cap(&self) -> u64 {
+        self.reserved.saturating_sub(self.penalty())
+    }
+
+    /// What the bankroll forfeits for a bad or missing board; v1 boards cannot be opened, so none
+    pub fn penalty(&self) -> u64 {
+        if self.legacy_board {
+            0
+        } else {
+            board_penalty(self.bet_amount)
+        }
+    }
+
+    /// Leaves are padded to the next power of two, so depth = ceil(log2(tiles))
+    pub fn merkle_depth(&self) -> usize {
+        (self.tiles() as u32).next_power_of_two().trailing_zeros() as usize
+    }
+}
+
+/// Game account layout of the v1 program, read only by `migrate_game`
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MinesGameV1 {
+    pub player: Pubkey,
+    pub house: Pubkey,
+    pub bet_amount: u64,
+    pub num_mines: u8,
+    pub mine_positions: [bool; LEGACY_BOARD_SIZE], // never set once per-tile proofs replaced the global reveal
+    pub revealed_tiles: [bool; LEGACY_BOARD_SIZE],
+    pub is_active: bool,
+    pub lost: bool,
+    pub revealed_count: u8,
+    pub commitment: [u8; 32],
+    pub state: u8,
+    pub started_at: i64,
+    pub expiry_ts: i64,
+}
+
+impl MinesGameV1 {
+    pub const LEN: usize = 32 + 32 + 8 + 1 + LEGACY_BOARD_SIZE + LEGACY_BOARD_SIZE + 1 + 1 + 1 + 32 + 1 + 8 + 8;
+}
+
+/// Per-game stake vault of the v1 program; only `migrate_game` still opens one
+#[account]
+pub struct Vault {
+    pub bump: u8,
+}
+impl Vault {
+    pub const LEN: usize = 1;
+}
+
+/// Player-owned responsible gaming limits. The rules are shared by every program in
+/// `casino_common::limits`; this account only ties them to the player.
+#[account]
+pub struct PlayerLimits {
+    pub player: Pubkey,
+    pub limits: LimitState,
+    pub bump: u8,
+}
+
+impl PlayerLimits {
+    pub const LEN: usize = 32 + LimitState::LEN + 1;
+    
+    /// Fills in the owner of a freshly created account
+    fn init_if_new(&mut self, player: Pubkey, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.bump = bump;
+        }
+    }
+}
+
+impl From<LimitViolation> for CustomError {
+    fn from(violation: LimitViolation) -> Self {
+        match violation {
+            LimitViolation::SelfExcluded => CustomError::SelfExcluded,
+            LimitViolation::CooldownActive => CustomError::CooldownActive,
+            LimitViolation::AboveBetLimit => CustomError::AboveBetLimit,
+            LimitViolation::LossLimitReached => CustomError::LossLimitReached,
+            LimitViolation::CurrencyNotLimited => CustomError::CurrencyNotLimited,
+            LimitViolation::InvalidSettings => CustomError::InvalidLimits,
+            LimitViolation::Overflow => CustomError::Overflow,
+        }
+    }
+}
+
+#[event]
+pub struct GameStarted {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub house: Pubkey,
+    pub mint: Pubkey, // default for SOL
+    pub bet_amount: u64,
+    pub num_mines: u8,
+    pub rows: u8,
+    pub cols: u8,
+    pub commitment: [u8; 32],
+    pub expiry_ts: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TilesRequested {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub tiles: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TileRevealed {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub tile_index: u8,
+    pub is_mine: bool,
+    pub revealed_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CashedOut {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub mint: Pubkey, // default for SOL
+    pub bet_amount: u64,
+    pub payout: u64,
+    pub revealed_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AbandonedGameSettled {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub house: Pubkey,
+    pub mint: Pubkey, // default for SOL
+    pub payout: u64,
+    pub revealed_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HouseCollected {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub house: Pubkey,
+    pub amount: u64,
+    pub revealed_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BoardFinalized {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub house: Pubkey,
+    pub mint: Pubkey, // default for SOL
+    pub valid: bool,
+    pub mines: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GameAborted {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub mint: Pubkey, // default for SOL
+    pub refund: u64,
+    pub revealed_count: u8,
+    pub timestamp: i64,
 }
 
 #[error_code]
@@ -303,19 +1569,362 @@ pub enum CustomError {
     NothingToCashOut,
     #[msg("Game not expired yet")]
     NotExpired,
+    #[msg("House edge is too high")]
+    InvalidHouseEdge,
+    #[msg("Bankroll cannot cover the maximum payout")]
+    InsufficientBankroll,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Game is currently paused")]
+    GamePaused,
+    #[msg("Bet amount is too low")]
+    BetTooLow,
+    #[msg("Bet amount is too high")]
+    BetTooHigh,
+    #[msg("Invalid bet range")]
+    InvalidBetRange,
+    #[msg("House does not match the config")]
+    InvalidHouse,
+    #[msg("Game is not waiting for the board")]
+    GameNotOver,
+    #[msg("Board must be between 2x2 and 8x8")]
+    InvalidBoardSize,
+    #[msg("Merkle proof length does not match the board")]
+    InvalidProofLength,
+    #[msg("Expiry is outside the configured bounds")]
+    InvalidExpiry,
+    #[msg("Account is not a v1 game")]
+    NotLegacyGame,
+    #[msg("Token accounts are required for games in an SPL mint")]
+    TokenAccountsMissing,
+    #[msg("Token account does not match the token bankroll")]
+    InvalidTokenAccount,
+    #[msg("Player is self-excluded")]
+    SelfExcluded,
+    #[msg("Player's session cooldown has not passed")]
+    CooldownActive,
+    #[msg("Bet is above the player's max bet")]
+    AboveBetLimit,
+    #[msg("Bet would exceed the player's loss limit")]
+    LossLimitReached,
+    #[msg("Payout exceeds the table's max payout")]
+    PayoutExceedsCap,
+    #[msg("Game has expired")]
+    GameExpired,
+    #[msg("A requested tile has not been opened yet")]
+    RevealPending,
+    #[msg("Tile was not requested by the player")]
+    TileNotRequested,
+    #[msg("Mints with a transfer fee are not supported")]
+    TransferFeeMint,
+    #[msg("Player's limits do not cover this currency")]
+    CurrencyNotLimited,
+    #[msg("Invalid limits. Up to 4 currencies, each listed once")]
+    InvalidLimits,
 }
 
 // Helpers
+
+/// Validated token accounts for one game, borrowed out of `TokenAccounts`
+struct TokenLeg<'a, 'info> {
+    bankroll: &'a mut Account<'info, TokenBankroll>,
+    vault: &'a InterfaceAccount<'info, TokenAccount>,
+    player_account: &'a InterfaceAccount<'info, TokenAccount>,
+    mint: &'a InterfaceAccount<'info, Mint>,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'info> TokenAccounts<'info> {
+    /// Mint of the passed token accounts, or the default pubkey (SOL) when none are passed
+    fn mint_key(&self) -> Pubkey {
+        self.mint.as_ref().map_or(Pubkey::default(), |mint| mint.key())
+    }
+
+    /// Checks that every account is present and belongs to `mint`'s token bankroll and `player`
+    fn leg(&mut self, mint: Pubkey, player: Pubkey) -> Result<TokenLeg<'_, 'info>> {
+        let (Some(bankroll), Some(vault), Some(player_account), Some(mint_account), Some(token_program)) = (
+            self.token_bankroll.as_mut(),
+            self.bankroll_token_account.as_ref(),
+            self.player_token_account.as_ref(),
+            self.mint.as_ref(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(CustomError::TokenAccountsMissing);
+        };
+        require_keys_eq!(mint_account.key(), mint, CustomError::InvalidTokenAccount);
+        require_keys_eq!(bankroll.mint, mint, CustomError::InvalidTokenAccount);
+        require_keys_eq!(bankroll.vault, vault.key(), CustomError::InvalidTokenAccount);
+        require_keys_eq!(player_account.mint, mint, CustomError::InvalidTokenAccount);
+        require_keys_eq!(player_account.owner, player, CustomError::InvalidTokenAccount);
+        Ok(TokenLeg {
+            bankroll,
+            vault,
+            player_account,
+            mint: mint_account,
+            token_program,
+        })
+    }
+}
+
+impl<'info> TokenLeg<'_, 'info> {
+    /// Moves `amount` from the player's token account into the vault
+    fn deposit(&self, player: &Signer<'info>, amount: u64) -> Result<()> {
+        token_interface::transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.player_account.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: player.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+
+    /// Pays `amount` from the vault to the player's token account, signed by the token bankroll
+    fn pay(&self, amount: u64) -> Result<()> {
+        let mint = self.bankroll.mint;
+        let seeds: &[&[u8]] = &[b"token_bankroll", mint.as_ref(), &[self.bankroll.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.player_account.to_account_info(),
+                    authority: self.bankroll.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+}
+
+/// Ends an active game with `payout` to the player. Only the bad-board penalty stays
+/// reserved until the house opens the board.
+fn pay_out_game<'info>(
+    game: &mut Account<'info, MinesGame>,
+    bankroll: &mut Account<'info, Bankroll>,
+    token: &mut TokenAccounts<'info>,
+    player_limits: &mut Account<'info, PlayerLimits>,
+    player: &AccountInfo<'info>,
+    payout: u64,
+    now: i64,
+) -> Result<()> {
+    game.is_active = false;
+    game.state = STATE_AWAITING_BOARD;
+    game.expiry_ts = now.saturating_add(BOARD_REVEAL_SECS);
+
+    let penalty = game.penalty();
+    release_and_pay(
+        game,
+        bankroll,
+        token,
+        player_limits,
+        player,
+        game.reserved.saturating_sub(penalty),
+        payout,
+        0,
+    )?;
+    game.reserved = penalty;
+    Ok(())
+}
+
+/// Releases `release` of the game's reservation and pays `payout` to the player, from the
+/// SOL bankroll or, for a token game, the mint's token bankroll. `stake_returned` is the part
+/// of `payout` that hands back an unplayed stake; a token bankroll takes it off the wagered
+/// total instead of counting it as paid out.
+/// The payout is credited against the player's losses in the game's currency.
+#[allow(clippy::too_many_arguments)]
+fn release_and_pay<'info>(
+    game: &MinesGame,
+    bankroll: &mut Account<'info, Bankroll>,
+    token: &mut TokenAccounts<'info>,
+    player_limits: &mut Account<'info, PlayerLimits>,
+    player: &AccountInfo<'info>,
+    release: u64,
+    payout: u64,
+    stake_returned: u64,
+) -> Result<()> {
+    if game.mint == Pubkey::default() {
+        bankroll.reserved = bankroll.reserved.saturating_sub(release);
+        if payout > 0 {
+            **bankroll.to_account_info().try_borrow_mut_lamports()? -= payout;
+            **player.try_borrow_mut_lamports()? += payout;
+        }
+    } else {
+        let leg = token.leg(game.mint, game.player)?;
+        leg.bankroll.reserved = leg.bankroll.reserved.saturating_sub(release);
+        leg.bankroll.total_wagered = leg.bankroll.total_wagered.saturating_sub(stake_returned);
+        leg.bankroll.total_paid_out = leg
+            .bankroll
+            .total_paid_out
+            .saturating_add(payout.saturating_sub(stake_returned));
+        if payout > 0 {
+            leg.pay(payout)?;
+        }
+    }
+    player_limits.limits.record_payout(Clock::get()?.unix_timestamp, game.mint, payout);
+    Ok(())
+}
+
+/// Verifies `tiles` against the committed root with one multiproof and records them in order,
+/// stopping at the first mine. Returns the bitmap of the tiles given.
+fn open_tiles(game: &mut Account<MinesGame>, tiles: &[TileOpening], nodes: &[[u8; 32]], now: i64) -> Result<u64> {
+    require!(game.is_active, CustomError::GameNotActive);
+    require!(game.state == STATE_COMMITTED, CustomError::GameNotRevealed);
+    require!(!tiles.is_empty(), CustomError::InvalidTileIndex);
+
+    let mut picked = 0u64;
+    for opening in tiles {
+        require!(opening.tile_index < game.tiles(), CustomError::InvalidTileIndex);
+        let bit = 1 << opening.tile_index;
+        require!((game.revealed | picked) & bit == 0, CustomError::TileAlreadyRevealed);
+        picked |= bit;
+    }
+
+    // Verify all leaves against the committed root
+    let leaves = tiles
+        .iter()
+        .map(|t| (t.tile_index as u64, compute_leaf_hash(t.tile_index, t.is_mine, &t.leaf_nonce)))
+        .collect();
+    let root = compute_multiproof_root(leaves, nodes, game.merkle_depth());
+    require!(root == Some(game.commitment), CustomError::InvalidCommitment);
+
+    for opening in tiles {
+        if record_reveal(game, opening.tile_index, opening.is_mine != 0, now) {
+            break;
+        }
+    }
+    Ok(picked)
+}
+
+/// Marks a verified tile as revealed and ends the game on a mine; returns whether it was a mine
+fn record_reveal(game: &mut Account<MinesGame>, tile_index: u8, is_mine: bool, now: i64) -> bool {
+    game.revealed |= 1 << tile_index;
+    game.revealed_count = game.revealed_count.saturating_add(1);
+    game.requested &= !(1 << tile_index);
+
+    if is_mine {
+        // player hit a mine => game lost, pending the house opening the board
+        game.requested = 0;
+        game.is_active = false;
+        game.lost = true;
+        game.state = STATE_AWAITING_BOARD;
+        game.expiry_ts = now.saturating_add(BOARD_REVEAL_SECS);
+    }
+
+    emit!(TileRevealed {
+        game: game.key(),
+        player: game.player,
+        tile_index,
+        is_mine,
+        revealed_count: game.revealed_count,
+        timestamp: now,
+    });
+    is_mine
+}
+
+/// What the bankroll forfeits when the house fails to open an honest board
+fn board_penalty(bet_amount: u64) -> u64 {
+    (bet_amount as u128 * BOARD_PENALTY_BPS / BPS_DENOMINATOR) as u64
+}
+
+/// Owed to the player for a bad or missing board: the lost stake (a cashed-out player
+/// was already paid) plus the penalty. A v1 board was never meant to be opened, so a
+/// migrated game that was lost stays lost.
+fn board_refund(game: &MinesGame) -> u64 {
+    let stake = if game.lost && !game.legacy_board { game.bet_amount } else { 0 };
+    stake.saturating_add(game.penalty())
+}
+
+/// Cash-out value after `safe_revealed` safe picks on a board of `tiles`: the stake times
+/// the fair odds C(n, k) / C(n - mines, k), less the house edge, and at most `cap`.
+/// Exact for every bet: the stake is applied with `mul_div`, so large bets on long odds
+/// don't overflow the intermediate product.
+fn payout_for(
+    bet_amount: u64,
+    tiles: u8,
+    num_mines: u8,
+    safe_revealed: u8,
+    house_edge_bps: u16,
+    cap: u64,
+) -> Result<u64> {
+    let board = tiles as u128;
+    let picks = safe_revealed as u128;
+    let safe_tiles = board
+        .checked_sub(num_mines as u128)
+        .filter(|safe| *safe >= picks)
+        .ok_or(CustomError::InvalidNumMines)?;
+
+    let numerator = binomial(board, picks)
+        .checked_mul(BPS_DENOMINATOR - house_edge_bps as u128)
+        .ok_or(CustomError::Overflow)?;
+    let denominator = binomial(safe_tiles, picks)
+        .checked_mul(BPS_DENOMINATOR)
+        .ok_or(CustomError::Overflow)?;
+    let payout = mul_div(bet_amount, numerator, denominator).ok_or(CustomError::Overflow)?;
+    Ok(payout.min(cap as u128) as u64)
+}
+
+/// floor(value * numerator / denominator) without forming the full product: `value` is
+/// split into 32-bit halves and the high half's remainder carried into the low half.
+/// Fits for any u64 value while both other operands stay below 2^96, which C(64, k) * 1e4 does.
+fn mul_div(value: u64, numerator: u128, denominator: u128) -> Option<u128> {
+    let (high, low) = ((value >> 32) as u128, (value & u32::MAX as u64) as u128);
+    let high_product = high.checked_mul(numerator)?;
+    let (high_quotient, high_remainder) = (high_product / denominator, high_product % denominator);
+    let low_part = high_remainder
+        .checked_mul(1 << 32)?
+        .checked_add(low.checked_mul(numerator)?)?;
+    high_quotient
+        .checked_mul(1 << 32)?
+        .checked_add(low_part / denominator)
+}
+
+/// n choose k; exact at every step because each partial product is itself a binomial
+fn binomial(n: u128, k: u128) -> u128 {
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
 fn compute_leaf_hash(tile_index: u8, is_mine: u8, leaf_nonce: &[u8; 32]) -> [u8; 32] {
     let ti = (tile_index as u32).to_le_bytes();
     let im = [is_mine];
     hashv(&[b"leaf", &ti[..], &im[..], &leaf_nonce[..]]).to_bytes()
 }
 
-fn compute_merkle_root(current: [u8; 32], proof: &[[u8; 32]; MERKLE_DEPTH], path_bits: u32) -> [u8; 32] {
+fn compute_leaf_nonce(board_seed: &[u8; 32], tile_index: u8) -> [u8; 32] {
+    let ti = (tile_index as u32).to_le_bytes();
+    hashv(&[b"nonce", &board_seed[..], &ti[..]]).to_bytes()
+}
+
+/// Root over all 2^depth leaves; slots past the board are safe filler leaves
+fn compute_board_root(board_seed: &[u8; 32], mines: u64, depth: usize) -> [u8; 32] {
+    let mut level: Vec<[u8; 32]> = (0..1u8 << depth)
+        .map(|i| {
+            let is_mine = ((mines >> i) & 1) as u8;
+            compute_leaf_hash(i, is_mine, &compute_leaf_nonce(board_seed, i))
+        })
+        .collect();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| hashv(&[&pair[0][..], &pair[1][..]]).to_bytes())
+            .collect();
+    }
+    level[0]
+}
+
+fn compute_merkle_root(current: [u8; 32], proof: &[[u8; 32]], path_bits: u32) -> [u8; 32] {
     let mut hash = current;
-    for level in 0..MERKLE_DEPTH {
-        let sibling = proof[level];
+    for (level, sibling) in proof.iter().enumerate() {
         let is_right = ((path_bits >> level) & 1) == 1;
         hash = if is_right {
             // current is right child
@@ -327,3 +1936,36 @@ fn compute_merkle_root(current: [u8; 32], proof: &[[u8; 32]; MERKLE_DEPTH], path
     }
     hash
 }
+
+/// Root from several (leaf index, leaf hash) pairs plus the missing siblings in `nodes`.
+/// Walks up one level at a time; a sibling that is itself known is used directly, otherwise the
+/// next node is consumed. Returns None if `nodes` runs short or has leftovers.
+fn compute_multiproof_root(mut level: Vec<(u64, [u8; 32])>, nodes: &[[u8; 32]], depth: usize) -> Option<[u8; 32]> {
+    level.sort_by_key(|(index, _)| *index);
+    let mut nodes = nodes.iter();
+    for _ in 0..depth {
+        let mut next = Vec::with_capacity(level.len());
+        let mut i = 0;
+        while i < level.len() {
+            let (index, hash) = level[i];
+            let sibling = if i + 1 < level.len() && level[i + 1].0 == index ^ 1 {
+                i += 1;
+                level[i].1
+            } else {
+                *nodes.next()?
+            };
+            let parent = if index & 1 == 1 {
+                hashv(&[&sibling[..], &hash[..]]).to_bytes()
+            } else {
+                hashv(&[&hash[..], &sibling[..]]).to_bytes()
+            };
+            next.push((index >> 1, parent));
+            i += 1;
+        }
+        level = next;
+    }
+    match nodes.next() {
+        Some(_) => None,
+        None => level.first().map(|(_, root)| *root),
+    }
+}
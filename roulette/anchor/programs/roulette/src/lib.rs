@@ -1,158 +1,1036 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv; // commit-reveal hashing
+use anchor_lang::system_program;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use casino_common::limits::{LimitSettings, LimitState, LimitViolation};
+use casino_common::token::has_transfer_fee;
 
 declare_id!("JAVuBXeBZqXNtS73azhBDAoYaaAFfo4gWXoZe2e7Jf8H");
 
+// Constants
+const DOUBLE_ZERO: u8 = 37; // the 00 pocket on double-zero wheels
+const SETTLE_WINDOW_SLOTS: u64 = 512; // SlotHashes only keeps the last 512 slots
+const MAX_BETS: usize = 16; // chips per spin; bounded by account space and tx size
+const HISTORY_LEN: usize = 10; // settled spins kept in each player's game account
+
 #[program]
 pub mod roulette {
     use super::*;
 
-    pub fn play_roulette(
-        ctx: Context<PlayRoulette>,
-        bet_amount: u64,
-        bet_type: BetType,
-        bet_value: u8, // Depends on bet_type
+    /// Creates the table config; only the house vault authority can do this
+    pub fn initialize_config(ctx: Context<InitializeConfig>, variant: WheelVariant) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.min_bet = 1_000_000; // 0.001 SOL in lamports
+        config.max_bet = 1_000_000_000; // 1 SOL in lamports
+        config.max_exposure = 36_000_000_000; // one max straight-up win
+        config.is_paused = false;
+        config.variant = variant;
+        config.even_money_rule = EvenMoneyRule::Standard;
+        config.bump = ctx.bumps.config;
+
+        msg!("Roulette config initialized for {:?} wheel", variant);
+        Ok(())
+    }
+
+    /// Updates table settings (bet limits, exposure cap, pause state, wheel variant, zero rule)
+    pub fn update_settings(
+        ctx: Context<UpdateSettings>,
+        min_bet: Option<u64>,
+        max_bet: Option<u64>,
+        max_exposure: Option<u64>,
+        is_paused: Option<bool>,
+        variant: Option<WheelVariant>,
+        even_money_rule: Option<EvenMoneyRule>,
     ) -> Result<()> {
-        require!(bet_amount > 0, CustomError::InvalidBetAmount);
-        // Only check bet_value for Number bets
-        if let BetType::Number = bet_type {
-            require!(bet_value <= 36, CustomError::InvalidBetValue);
+        let config = &mut ctx.accounts.config;
+
+        // Apply min/max updates atomically to avoid invalid states
+        if min_bet.is_some() || max_bet.is_some() {
+            let new_min = min_bet.unwrap_or(config.min_bet);
+            let new_max = max_bet.unwrap_or(config.max_bet);
+            require!(new_min > 0, CustomError::InvalidBetRange);
+            require!(new_max >= new_min, CustomError::InvalidBetRange);
+            config.min_bet = new_min;
+            config.max_bet = new_max;
         }
 
-        // Save keys and account infos before mutable borrow
-        let game_key = ctx.accounts.game.key();
-        let game_account_info = ctx.accounts.game.to_account_info();
-        let player_key = ctx.accounts.player.key();
-        let player_account_info = ctx.accounts.player.to_account_info();
-        let system_program_info = ctx.accounts.system_program.to_account_info();
+        if let Some(exposure) = max_exposure {
+            require!(exposure > 0, CustomError::InvalidAmount);
+            config.max_exposure = exposure;
+        }
 
-        let clock = Clock::get()?;
-        // WARNING: This is NOT secure randomness. For production, use a VRF oracle (e.g., Switchboard, Chainlink VRF).
-        let spin_result = (clock.unix_timestamp % 37) as u8; // 0 to 36
-
-        // Transfer SOL from player to contract using system program CPI
-        let transfer_ix = anchor_lang::solana_program::system_instruction::transfer(
-            &player_key,
-            &game_key,
-            bet_amount,
+        if let Some(paused) = is_paused {
+            config.is_paused = paused;
+        }
+
+        // Unsettled games keep the variant and rule they were placed under
+        if let Some(variant) = variant {
+            config.variant = variant;
+        }
+
+        if let Some(rule) = even_money_rule {
+            config.even_money_rule = rule;
+        }
+
+        msg!(
+            "Settings updated: min_bet={:?}, max_bet={:?}, max_exposure={:?}, is_paused={:?}, variant={:?}, even_money_rule={:?}",
+            min_bet,
+            max_bet,
+            max_exposure,
+            is_paused,
+            variant,
+            even_money_rule
         );
-        anchor_lang::solana_program::program::invoke(
-            &transfer_ix,
-            &[
-                player_account_info.clone(),
-                game_account_info.clone(),
-                system_program_info.clone(),
-            ],
+        Ok(())
+    }
+
+    /// Creates the program-owned house vault that takes bets and pays winnings
+    pub fn initialize_house(ctx: Context<InitializeHouse>) -> Result<()> {
+        let house_vault = &mut ctx.accounts.house_vault;
+        house_vault.authority = ctx.accounts.authority.key();
+        house_vault.reserved = 0;
+        house_vault.bump = ctx.bumps.house_vault;
+
+        msg!("House vault initialized for {}", house_vault.authority);
+        Ok(())
+    }
+
+    /// Funds the house vault
+    pub fn fund_house(ctx: Context<FundHouse>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidAmount);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.house_vault.to_account_info(),
+                },
+            ),
+            amount,
         )?;
 
-        // Now safe to mutably borrow game
-        let game = &mut ctx.accounts.game;
+        msg!("Funded house vault with {} lamports", amount);
+        Ok(())
+    }
 
-        let mut won = false;
-        let mut payout_multiplier = 0;
+    /// Withdraws free funds from the house vault; lamports reserved for unsettled bets stay put
+    pub fn withdraw_house(ctx: Context<WithdrawHouse>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidAmount);
 
-        match bet_type {
-            BetType::Red => {
-                if is_red(spin_result) {
-                    won = true;
-                    payout_multiplier = 2;
-                }
-            }
-            BetType::Black => {
-                if is_black(spin_result) {
-                    won = true;
-                    payout_multiplier = 2;
-                }
-            }
-            BetType::Odd => {
-                if spin_result != 0 && spin_result % 2 == 1 {
-                    won = true;
-                    payout_multiplier = 2;
-                }
-            }
-            BetType::Even => {
-                if spin_result != 0 && spin_result % 2 == 0 {
-                    won = true;
-                    payout_multiplier = 2;
-                }
-            }
-            BetType::High => {
-                if (19..=36).contains(&spin_result) {
-                    won = true;
-                    payout_multiplier = 2;
-                }
+        let house_vault = &ctx.accounts.house_vault;
+        let min_rent_balance = Rent::get()?.minimum_balance(8 + HouseVault::LEN);
+        require!(
+            house_vault
+                .to_account_info()
+                .lamports()
+                .saturating_sub(house_vault.reserved)
+                .saturating_sub(amount)
+                >= min_rent_balance,
+            CustomError::InsufficientHouseFunds
+        );
+
+        **ctx.accounts.house_vault.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        msg!("Withdrew {} lamports from house vault", amount);
+        Ok(())
+    }
+
+    /// Whitelists an SPL mint (Token or Token-2022) as bet currency with its own vault and limits.
+    /// Only the house vault authority can do this. Mints with transfer fees are not supported.
+    pub fn initialize_token_house(
+        ctx: Context<InitializeTokenHouse>,
+        min_bet: u64,
+        max_bet: u64,
+        max_exposure: u64,
+    ) -> Result<()> {
+        require!(min_bet > 0 && max_bet >= min_bet, CustomError::InvalidBetRange);
+        require!(max_exposure > 0, CustomError::InvalidAmount);
+        require!(
+            !has_transfer_fee(&ctx.accounts.mint.to_account_info())?,
+            CustomError::TransferFeeMint
+        );
+
+        let token_house = &mut ctx.accounts.token_house;
+        token_house.authority = ctx.accounts.authority.key();
+        token_house.mint = ctx.accounts.mint.key();
+        token_house.vault = ctx.accounts.token_vault.key();
+        token_house.min_bet = min_bet;
+        token_house.max_bet = max_bet;
+        token_house.max_exposure = max_exposure;
+        token_house.reserved = 0;
+        token_house.total_wagered = 0;
+        token_house.total_paid_out = 0;
+        token_house.bump = ctx.bumps.token_house;
+
+        msg!("Token house initialized for mint {}", token_house.mint);
+        Ok(())
+    }
+
+    /// Updates a token house's bet limits and exposure cap, in the mint's base units
+    pub fn update_token_house(
+        ctx: Context<UpdateTokenHouse>,
+        min_bet: Option<u64>,
+        max_bet: Option<u64>,
+        max_exposure: Option<u64>,
+    ) -> Result<()> {
+        let token_house = &mut ctx.accounts.token_house;
+
+        // Apply min/max updates atomically to avoid invalid states
+        if min_bet.is_some() || max_bet.is_some() {
+            let new_min = min_bet.unwrap_or(token_house.min_bet);
+            let new_max = max_bet.unwrap_or(token_house.max_bet);
+            require!(new_min > 0, CustomError::InvalidBetRange);
+            require!(new_max >= new_min, CustomError::InvalidBetRange);
+            token_house.min_bet = new_min;
+            token_house.max_bet = new_max;
+        }
+
+        if let Some(exposure) = max_exposure {
+            require!(exposure > 0, CustomError::InvalidAmount);
+            token_house.max_exposure = exposure;
+        }
+
+        msg!(
+            "Token house {} updated: min_bet={:?}, max_bet={:?}, max_exposure={:?}",
+            token_house.mint,
+            min_bet,
+            max_bet,
+            max_exposure
+        );
+        Ok(())
+    }
+
+    /// Funds a token house vault
+    pub fn fund_token_house(ctx: Context<FundTokenHouse>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidAmount);
+
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.funder_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.token_vault.to_account_info(),
+                    authority: ctx.accounts.funder.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        msg!("Funded token house {} with {}", ctx.accounts.mint.key(), amount);
+        Ok(())
+    }
+
+    /// Withdraws free tokens from a token house vault; tokens reserved for unsettled bets stay put
+    pub fn withdraw_token_house(ctx: Context<WithdrawTokenHouse>, amount: u64) -> Result<()> {
+        require!(amount > 0, CustomError::InvalidAmount);
+
+        let token_house = &ctx.accounts.token_house;
+        require!(
+            ctx.accounts
+                .token_vault
+                .amount
+                .saturating_sub(token_house.reserved)
+                >= amount,
+            CustomError::InsufficientHouseFunds
+        );
+
+        let mint = token_house.mint;
+        let seeds: &[&[u8]] = &[b"token_house", mint.as_ref(), &[token_house.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.token_vault.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.authority_token_account.to_account_info(),
+                    authority: token_house.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        msg!("Withdrew {} from token house {}", amount, mint);
+        Ok(())
+    }
+
+    /// Phase 1: player places a single bet.
+    /// - house co-signs and supplies `house_commitment` = hash(house_seed)
+    /// - player supplies their own `player_seed`
+    /// - the stake moves into the house vault, which reserves the worst-case payout
+    /// - the spin is drawn later in `settle_bet` from both seeds and the hash of the bet slot
+    /// Passing the `token` accounts stakes the bet in that mint instead of SOL.
+    pub fn place_bet(
+        ctx: Context<PlaceBet>,
+        bet_amount: u64,
+        bet_type: BetType,
+        house_commitment: [u8; 32],
+        player_seed: [u8; 32],
+    ) -> Result<()> {
+        let bet = Bet {
+            bet_type,
+            amount: bet_amount,
+        };
+        accept_bets(ctx, vec![bet], house_commitment, player_seed)
+    }
+
+    /// Phase 1 for a whole table layout: up to MAX_BETS bets, each with its own amount,
+    /// all settled against the same spin. Bets imprisoned by the previous spin on this
+    /// game account ride along and count towards MAX_BETS.
+    pub fn place_bets(
+        ctx: Context<PlaceBet>,
+        bets: Vec<Bet>,
+        house_commitment: [u8; 32],
+        player_seed: [u8; 32],
+    ) -> Result<()> {
+        accept_bets(ctx, bets, house_commitment, player_seed)
+    }
+
+    /// Phase 2: house reveals its seed and every bet is settled against one result.
+    /// spin_result = u64_le(hash(house_seed || player_seed || slot_hash(bet_slot))[..8]) % pockets
+    pub fn settle_bet(ctx: Context<SettleBet>, house_seed: [u8; 32]) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(!game.is_finished, CustomError::BetAlreadySettled);
+        require!(
+            hashv(&[&house_seed[..]]).to_bytes() == game.house_commitment,
+            CustomError::InvalidHouseSeed
+        );
+
+        let clock = Clock::get()?;
+        require!(clock.slot > game.bet_slot, CustomError::SlotHashUnavailable);
+        let slot_hash = {
+            let data = ctx.accounts.recent_slothashes.try_borrow_data()?;
+            find_slot_hash(&data, game.bet_slot).ok_or(CustomError::SlotHashUnavailable)?
+        };
+
+        let spin_result = derive_spin_result(game.variant, &house_seed, &game.player_seed, &slot_hash);
+
+        // Settle each bet and sum the winnings so the vault pays once
+        let mut outcomes = Vec::with_capacity(game.bets.len());
+        let mut payout: u64 = 0;
+        let mut imprisoned: u64 = 0;
+        for bet in game.bets.iter() {
+            let outcome = resolve(bet, spin_result, game.even_money_rule)?;
+            payout = payout
+                .checked_add(outcome.payout)
+                .ok_or(CustomError::Overflow)?;
+            if outcome.imprisoned {
+                imprisoned = imprisoned
+                    .checked_add(outcome.amount)
+                    .ok_or(CustomError::Overflow)?;
             }
-            BetType::Low => {
-                if (1..=18).contains(&spin_result) {
-                    won = true;
-                    payout_multiplier = 2;
-                }
+            outcomes.push(outcome);
+        }
+
+        // Swap the spin's reservation for the stakes still held in prison,
+        // then pay out of the vault (program-owned, so lamports move directly)
+        if game.mint == Pubkey::default() {
+            let house_vault = &mut ctx.accounts.house_vault;
+            house_vault.reserved = house_vault
+                .reserved
+                .saturating_sub(game.reserved)
+                .checked_add(imprisoned)
+                .ok_or(CustomError::Overflow)?;
+            if payout > 0 {
+                **house_vault.to_account_info().try_borrow_mut_lamports()? -= payout;
+                **ctx.accounts.player.to_account_info().try_borrow_mut_lamports()? += payout;
             }
-            BetType::Number => {
-                if spin_result == bet_value {
-                    won = true;
-                    payout_multiplier = 36;
-                }
+        } else {
+            let leg = ctx.accounts.token.leg(game.mint, game.player)?;
+            leg.house.reserved = leg
+                .house
+                .reserved
+                .saturating_sub(game.reserved)
+                .checked_add(imprisoned)
+                .ok_or(CustomError::Overflow)?;
+            leg.house.total_paid_out = leg.house.total_paid_out.saturating_add(payout);
+            if payout > 0 {
+                leg.pay(payout)?;
             }
         }
+        ctx.accounts.player_limits.limits.record_payout(clock.unix_timestamp, game.mint, payout);
 
-        // Set game data
-        game.player = player_key;
-        game.bet_amount = bet_amount;
-        game.bet_type = bet_type;
-        game.bet_value = bet_value;
+        // Record everything needed to recompute the spin off-chain
+        let game = &mut ctx.accounts.game;
+        game.bets = outcomes;
+        game.house_seed = house_seed;
+        game.slot_hash = slot_hash;
         game.spin_result = spin_result;
-        game.won = won;
+        game.reserved = imprisoned;
+        game.payout = payout;
         game.is_finished = true;
 
-        // Payout if player won
-        if won {
-            let payout = bet_amount
-                .checked_mul(payout_multiplier)
-                .ok_or(CustomError::Overflow)?;
-            // Transfer payout from game to player using system program CPI
-            let payout_ix = anchor_lang::solana_program::system_instruction::transfer(
-                &game_key,
-                &player_key,
-                payout,
-            );
-            anchor_lang::solana_program::program::invoke(
-                &payout_ix,
-                &[
-                    game_account_info.clone(),
-                    player_account_info.clone(),
-                    system_program_info.clone(),
-                ],
-            )?;
+        // Ring buffer: overwrite the oldest of the last HISTORY_LEN spins
+        let index = (game.total_spins % HISTORY_LEN as u64) as usize;
+        game.history[index] = SpinRecord {
+            bet_slot: game.bet_slot,
+            spin_result,
+            total_wagered: game.total_wagered,
+            payout,
+            settled_at: clock.unix_timestamp,
+        };
+        game.total_spins = game.total_spins.saturating_add(1);
+
+        emit!(BetSettled {
+            game: game.key(),
+            player: game.player,
+            house: game.house,
+            mint: game.mint,
+            bets: game.bets.clone(),
+            total_wagered: game.total_wagered,
+            spin_result,
+            payout,
+            imprisoned,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// If the house never reveals its seed while the bet slot hash is still available,
+    /// the player is paid the spin's worst-case payout (never less than the stakes, imprisoned
+    /// ones included) from the reservation. Once the bet slot passes the house can work out the
+    /// spin, so withholding a winning one must cost it at least as much as paying it.
+    /// The game account stays open for the next spin.
+    pub fn refund_bet(ctx: Context<RefundBet>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(!game.is_finished, CustomError::BetAlreadySettled);
+        let clock = Clock::get()?;
+        require!(
+            clock.slot > game.bet_slot.saturating_add(SETTLE_WINDOW_SLOTS),
+            CustomError::SettleWindowOpen
+        );
+
+        let mut stakes = game.total_wagered;
+        for bet in game.bets.iter().filter(|bet| bet.imprisoned) {
+            stakes = stakes.checked_add(bet.amount).ok_or(CustomError::Overflow)?;
         }
+        let refund = stakes.max(game.reserved);
+
+        if game.mint == Pubkey::default() {
+            let house_vault = &mut ctx.accounts.house_vault;
+            house_vault.reserved = house_vault.reserved.saturating_sub(game.reserved);
+            **house_vault.to_account_info().try_borrow_mut_lamports()? -= refund;
+            **ctx.accounts.player.to_account_info().try_borrow_mut_lamports()? += refund;
+        } else {
+            let leg = ctx.accounts.token.leg(game.mint, game.player)?;
+            leg.house.reserved = leg.house.reserved.saturating_sub(game.reserved);
+            // Settled as a forfeited spin: the stakes stay wagered and the whole refund is paid out
+            leg.house.total_paid_out = leg.house.total_paid_out.saturating_add(refund);
+            leg.pay(refund)?;
+        }
+        ctx.accounts.player_limits.limits.record_payout(clock.unix_timestamp, game.mint, refund);
+
+        let game = &mut ctx.accounts.game;
+        game.bets.clear();
+        game.reserved = 0;
+        game.is_finished = true;
+
+        emit!(BetRefunded {
+            game: game.key(),
+            player: game.player,
+            mint: game.mint,
+            refund,
+            timestamp: clock.unix_timestamp,
+        });
 
         Ok(())
     }
+
+    /// Player closes their game account and gets the rent back.
+    /// Only allowed once the last spin settled and no bets are held in prison.
+    pub fn close_game(ctx: Context<CloseGame>) -> Result<()> {
+        let game = &ctx.accounts.game;
+        require!(game.is_finished, CustomError::BetNotSettled);
+        require!(game.reserved == 0, CustomError::ImprisonedBetsPending);
+
+        // Anchor will close `game` to `player` (close = player), returning rent.
+        Ok(())
+    }
+
+    /// Sets the player's own limits. Tightening takes effect immediately;
+    /// loosening any limit only after LIMIT_LOOSENING_DELAY.
+    pub fn set_limits(ctx: Context<SetLimits>, settings: LimitSettings) -> Result<()> {
+        let player_limits = &mut ctx.accounts.player_limits;
+        player_limits.init_if_new(ctx.accounts.player.key(), ctx.bumps.player_limits);
+        player_limits
+            .limits
+            .request(Clock::get()?.unix_timestamp, settings)
+            .map_err(CustomError::from)?;
+
+        msg!("Limits updated; loosening pending until {}", player_limits.limits.pending_at);
+        Ok(())
+    }
+}
+
+/// Shared by `place_bet` and `place_bets`: enforces the table config, checks the vault can
+/// cover the worst pocket, takes the stakes and records the commitment.
+/// A game account is created on first use and can be reused once its last spin settled.
+/// SOL bets use the config limits and the house vault; token bets use the mint's token house.
+fn accept_bets(
+    ctx: Context<PlaceBet>,
+    bets: Vec<Bet>,
+    house_commitment: [u8; 32],
+    player_seed: [u8; 32],
+) -> Result<()> {
+    let config = &ctx.accounts.config;
+    require!(!config.is_paused, CustomError::GamePaused);
+    let mint = ctx.accounts.token.mint_key();
+    let player = ctx.accounts.player.key();
+    let (min_bet, max_bet, max_exposure) = if mint == Pubkey::default() {
+        (config.min_bet, config.max_bet, config.max_exposure)
+    } else {
+        let leg = ctx.accounts.token.leg(mint, player)?;
+        (leg.house.min_bet, leg.house.max_bet, leg.house.max_exposure)
+    };
+
+    // Carry imprisoned bets over from the previous spin on this account
+    let game = &ctx.accounts.game;
+    let mut outcomes: Vec<BetOutcome> = Vec::new();
+    let mut previously_reserved: u64 = 0;
+    if game.player != Pubkey::default() {
+        require!(game.is_finished, CustomError::BetNotSettled);
+        outcomes.extend(game.bets.iter().filter(|bet| bet.imprisoned).copied());
+        previously_reserved = game.reserved;
+        // Prison stakes are held in the previous spin's currency
+        require!(outcomes.is_empty() || game.mint == mint, CustomError::MintMismatch);
+    }
+    require!(
+        !(bets.is_empty() && outcomes.is_empty()) && bets.len() + outcomes.len() <= MAX_BETS,
+        CustomError::InvalidBetCount
+    );
+
+    let mut total_wagered: u64 = 0;
+    for bet in bets.iter() {
+        require!(bet.amount > 0, CustomError::InvalidBetAmount);
+        require!(bet.amount >= min_bet, CustomError::BetTooLow);
+        require!(bet.amount <= max_bet, CustomError::BetTooHigh);
+        bet.bet_type.validate(config.variant)?;
+        total_wagered = total_wagered
+            .checked_add(bet.amount)
+            .ok_or(CustomError::Overflow)?;
+    }
+
+    // The player's own limits; stakes count as lost in their currency until paid back
+    let largest_bet = bets.iter().map(|bet| bet.amount).max().unwrap_or(0);
+    let player_limits = &mut ctx.accounts.player_limits;
+    player_limits.init_if_new(player, ctx.bumps.player_limits);
+    player_limits.limits.record_play(
+        Clock::get()?.unix_timestamp,
+        mint,
+        total_wagered,
+        largest_bet,
+    ).map_err(CustomError::from)?;
+
+    outcomes.extend(bets.iter().map(|bet| BetOutcome {
+        bet_type: bet.bet_type,
+        amount: bet.amount,
+        won: false,
+        payout: 0,
+        imprisoned: false,
+    }));
+
+    // Cap the table's exposure, then ensure the house vault can cover it after receiving the bets
+    let variant = config.variant;
+    let even_money_rule = config.even_money_rule;
+    let max_payout = max_payout(variant, even_money_rule, &outcomes)?;
+    require!(max_payout <= max_exposure, CustomError::ExposureTooHigh);
+    if mint == Pubkey::default() {
+        let min_rent_balance = Rent::get()?.minimum_balance(8 + HouseVault::LEN);
+        let projected_vault_balance = ctx
+            .accounts
+            .house_vault
+            .to_account_info()
+            .lamports()
+            .saturating_add(total_wagered);
+        require!(
+            projected_vault_balance
+                .saturating_sub(
+                    ctx.accounts
+                        .house_vault
+                        .reserved
+                        .saturating_sub(previously_reserved),
+                )
+                .saturating_sub(max_payout)
+                >= min_rent_balance,
+            CustomError::InsufficientHouseFunds
+        );
+
+        // Transfer bets to house vault
+        if total_wagered > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: ctx.accounts.house_vault.to_account_info(),
+                    },
+                ),
+                total_wagered,
+            )?;
+        }
+
+        let house_vault = &mut ctx.accounts.house_vault;
+        house_vault.reserved = house_vault
+            .reserved
+            .saturating_sub(previously_reserved)
+            .checked_add(max_payout)
+            .ok_or(CustomError::Overflow)?;
+    } else {
+        let accounts = &mut *ctx.accounts;
+        let leg = accounts.token.leg(mint, player)?;
+        require!(
+            leg.vault
+                .amount
+                .saturating_add(total_wagered)
+                .saturating_sub(leg.house.reserved.saturating_sub(previously_reserved))
+                >= max_payout,
+            CustomError::InsufficientHouseFunds
+        );
+
+        // Transfer bets to the token house vault
+        if total_wagered > 0 {
+            leg.deposit(&accounts.player, total_wagered)?;
+        }
+
+        leg.house.reserved = leg
+            .house
+            .reserved
+            .saturating_sub(previously_reserved)
+            .checked_add(max_payout)
+            .ok_or(CustomError::Overflow)?;
+        leg.house.total_wagered = leg.house.total_wagered.saturating_add(total_wagered);
+    }
+
+    let game = &mut ctx.accounts.game;
+    game.player = ctx.accounts.player.key();
+    game.bump = ctx.bumps.game;
+    game.house = ctx.accounts.house.key();
+    game.mint = mint;
+    game.bets = outcomes;
+    game.variant = variant;
+    game.even_money_rule = even_money_rule;
+    game.total_wagered = total_wagered;
+    game.reserved = max_payout;
+    game.house_commitment = house_commitment;
+    game.player_seed = player_seed;
+    game.house_seed = [0u8; 32];
+    let clock = Clock::get()?;
+    game.bet_slot = clock.slot;
+    game.slot_hash = [0u8; 32];
+    game.spin_result = 0;
+    game.payout = 0;
+    game.is_finished = false;
+
+    emit!(BetPlaced {
+        game: game.key(),
+        player: game.player,
+        house: game.house,
+        mint,
+        bets: game.bets.clone(),
+        total_wagered,
+        max_payout,
+        house_commitment,
+        bet_slot: game.bet_slot,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitializeHouse<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + HouseVault::LEN,
+        seeds = [b"house_vault"],
+        bump
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundHouse<'info> {
+    #[account(mut, seeds = [b"house_vault"], bump = house_vault.bump)]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawHouse<'info> {
+    #[account(mut, seeds = [b"house_vault"], bump = house_vault.bump, has_one = authority)]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + RouletteConfig::LEN,
+        seeds = [b"roulette_config"],
+        bump
+    )]
+    pub config: Account<'info, RouletteConfig>,
+    #[account(seeds = [b"house_vault"], bump = house_vault.bump, has_one = authority)]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSettings<'info> {
+    #[account(mut, seeds = [b"roulette_config"], bump = config.bump, has_one = authority)]
+    pub config: Account<'info, RouletteConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBet<'info> {
+    // One game account per player, created on the first spin and reused afterwards
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + RouletteGame::LEN,
+        seeds = [b"roulette", player.key().as_ref()],
+        bump
+    )]
+    pub game: Account<'info, RouletteGame>,
+    #[account(seeds = [b"roulette_config"], bump = config.bump)]
+    pub config: Account<'info, RouletteConfig>,
+    #[account(
+        mut,
+        seeds = [b"house_vault"],
+        bump = house_vault.bump,
+        constraint = house_vault.authority == house.key()
+    )]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    /// House must co-sign so the commitment provably originates from it.
+    pub house: Signer<'info>,
+    // The player's own limits, created empty (no limits) on their first game
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + PlayerLimits::LEN,
+        seeds = [b"limits", player.key().as_ref()],
+        bump
+    )]
+    pub player_limits: Account<'info, PlayerLimits>,
+    pub token: TokenAccounts<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleBet<'info> {
+    #[account(
+        mut,
+        seeds = [b"roulette", player.key().as_ref()],
+        bump = game.bump,
+        has_one = house
+    )]
+    pub game: Account<'info, RouletteGame>,
+    #[account(mut, seeds = [b"house_vault"], bump = house_vault.bump)]
+    pub house_vault: Account<'info, HouseVault>,
+    /// CHECK: only receives the payout; the game PDA is derived from it
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+    /// House must sign to reveal its seed (must match game.house).
+    pub house: Signer<'info>,
+    /// CHECK: Sysvar for slot hashes
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::id())]
+    pub recent_slothashes: UncheckedAccount<'info>,
+    // The player's limits, to credit what is paid back; placing the game created them
+    #[account(mut, seeds = [b"limits", player.key().as_ref()], bump = player_limits.bump)]
+    pub player_limits: Account<'info, PlayerLimits>,
+    pub token: TokenAccounts<'info>,
 }
 
 #[derive(Accounts)]
-pub struct PlayRoulette<'info> {
-    #[account(init, payer = player, space = 8 + RouletteGame::LEN)]
+pub struct RefundBet<'info> {
+    #[account(mut, seeds = [b"roulette", player.key().as_ref()], bump = game.bump)]
     pub game: Account<'info, RouletteGame>,
+    #[account(mut, seeds = [b"house_vault"], bump = house_vault.bump)]
+    pub house_vault: Account<'info, HouseVault>,
     #[account(mut)]
     pub player: Signer<'info>,
+    // The player's limits, to credit what is paid back; placing the game created them
+    #[account(mut, seeds = [b"limits", player.key().as_ref()], bump = player_limits.bump)]
+    pub player_limits: Account<'info, PlayerLimits>,
+    pub token: TokenAccounts<'info>,
+}
+
+/// Accounts for a game staked in an SPL mint; all omitted for SOL games.
+/// They are checked against each other in `leg`, since any of them may be missing.
+#[derive(Accounts)]
+pub struct TokenAccounts<'info> {
+    #[account(mut)]
+    pub token_house: Option<Account<'info, TokenHouse>>,
+    #[account(mut)]
+    pub house_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub player_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTokenHouse<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenHouse::LEN,
+        seeds = [b"token_house", mint.key().as_ref()],
+        bump
+    )]
+    pub token_house: Account<'info, TokenHouse>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"token_vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_house,
+        token::token_program = token_program
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    // Only the SOL house authority can whitelist a mint
+    #[account(seeds = [b"house_vault"], bump = house_vault.bump, has_one = authority)]
+    pub house_vault: Account<'info, HouseVault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateTokenHouse<'info> {
+    #[account(
+        mut,
+        seeds = [b"token_house", token_house.mint.as_ref()],
+        bump = token_house.bump,
+        has_one = authority
+    )]
+    pub token_house: Account<'info, TokenHouse>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundTokenHouse<'info> {
+    #[account(
+        seeds = [b"token_house", mint.key().as_ref()],
+        bump = token_house.bump,
+        has_one = mint
+    )]
+    pub token_house: Account<'info, TokenHouse>,
+    #[account(mut, address = token_house.vault)]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub funder: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokenHouse<'info> {
+    #[account(
+        seeds = [b"token_house", mint.key().as_ref()],
+        bump = token_house.bump,
+        has_one = mint,
+        has_one = authority
+    )]
+    pub token_house: Account<'info, TokenHouse>,
+    #[account(mut, address = token_house.vault)]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct SetLimits<'info> {
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + PlayerLimits::LEN,
+        seeds = [b"limits", player.key().as_ref()],
+        bump
+    )]
+    pub player_limits: Account<'info, PlayerLimits>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGame<'info> {
+    // Close the game acct (rent) to player
+    #[account(
+        mut,
+        seeds = [b"roulette", player.key().as_ref()],
+        bump = game.bump,
+        close = player
+    )]
+    pub game: Account<'info, RouletteGame>,
+    #[account(mut)]
+    pub player: Signer<'info>,
+}
+
+#[account]
+pub struct HouseVault {
+    pub authority: Pubkey,
+    pub reserved: u64, // worst-case payouts owed to unsettled bets
+    pub bump: u8,
+}
+
+impl HouseVault {
+    pub const LEN: usize = 32 + 8 + 1;
+}
+
+/// House bankroll for one whitelisted SPL mint; amounts are in the mint's base units
+#[account]
+pub struct TokenHouse {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,        // token account owned by this PDA
+    pub min_bet: u64,         // per bet
+    pub max_bet: u64,         // per bet
+    pub max_exposure: u64,    // worst-case payout allowed on one spin
+    pub reserved: u64,        // worst-case payouts owed to unsettled bets
+    pub total_wagered: u64,
+    pub total_paid_out: u64,
+    pub bump: u8,
+}
+
+impl TokenHouse {
+    pub const LEN: usize = 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct RouletteConfig {
+    pub authority: Pubkey,
+    pub min_bet: u64,      // per bet, in lamports
+    pub max_bet: u64,      // per bet, in lamports
+    pub max_exposure: u64, // worst-case payout allowed on one spin
+    pub is_paused: bool,
+    pub variant: WheelVariant,
+    pub even_money_rule: EvenMoneyRule, // what happens to even-money bets on zero
+    pub bump: u8,
+}
+
+impl RouletteConfig {
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 1 + 1 + 1 + 1;
+}
+
 #[account]
 pub struct RouletteGame {
     pub player: Pubkey,
-    pub bet_amount: u64,
-    pub bet_type: BetType,
-    pub bet_value: u8,
+    pub house: Pubkey,
+    pub variant: WheelVariant,      // wheel the spin is drawn on, fixed at place_bet
+    pub even_money_rule: EvenMoneyRule,
+    pub bets: Vec<BetOutcome>,      // per-bet breakdown, filled in at settle_bet
+    pub total_wagered: u64,         // new stakes for this spin (imprisoned stakes excluded)
+    pub reserved: u64,              // held in the house vault for this game: worst-case payout
+                                    // while pending, imprisoned stakes once settled
+    pub house_commitment: [u8; 32], // hash(house_seed), fixed at place_bet
+    pub player_seed: [u8; 32],
+    pub house_seed: [u8; 32],       // revealed at settle_bet
+    pub bet_slot: u64,
+    pub slot_hash: [u8; 32],        // hash of bet_slot, read at settle_bet
     pub spin_result: u8,
-    pub won: bool,
+    pub payout: u64,                // total paid for the spin
     pub is_finished: bool,
+    pub total_spins: u64,           // settled spins; next history slot is total_spins % HISTORY_LEN
+    pub history: [SpinRecord; HISTORY_LEN],
+    pub bump: u8,
+    pub mint: Pubkey,               // bet currency of the current spin; default is SOL
 }
 
 impl RouletteGame {
-    pub const LEN: usize = 32 + 8 + 1 + 1 + 1 + 1 + 1; // ~45
+    pub const LEN: usize = 32 + 32 + 1 + 1 + (4 + MAX_BETS * BetOutcome::LEN) + 8 + 8 + 32 + 32 + 32 + 8 + 32 + 1 + 8 + 1
+        + 8 + HISTORY_LEN * SpinRecord::LEN + 1 + 32;
+}
+
+/// Summary of one settled spin kept in the game account's history
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpinRecord {
+    pub bet_slot: u64,
+    pub spin_result: u8,
+    pub total_wagered: u64,
+    pub payout: u64,
+    pub settled_at: i64,
+}
+
+impl SpinRecord {
+    pub const LEN: usize = 8 + 1 + 8 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WheelVariant {
+    SingleZero, // European: 0-36
+    DoubleZero, // American: 0, 00 and 1-36
+}
+
+impl WheelVariant {
+    pub fn pockets(&self) -> u8 {
+        match self {
+            WheelVariant::SingleZero => 37,
+            WheelVariant::DoubleZero => 38,
+        }
+    }
+}
+
+/// What happens to even-money bets (red/black, odd/even, high/low) when the ball lands on zero
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EvenMoneyRule {
+    Standard,  // the bet loses
+    LaPartage, // half the stake comes back
+    EnPrison,  // the stake is held for the next spin and returned if that spin wins
+}
+
+/// One chip placement as submitted by the player
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Bet {
+    pub bet_type: BetType,
+    pub amount: u64,
 }
 
+/// A placed bet and how it resolved
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BetOutcome {
+    pub bet_type: BetType,
+    pub amount: u64,
+    pub won: bool,
+    pub payout: u64,
+    pub imprisoned: bool, // En Prison: stake held for the next spin on this game account
+}
+
+impl BetOutcome {
+    pub const LEN: usize = BetType::LEN + 8 + 1 + 8 + 1;
+}
+
+/// Every bet carries its own selection; numbers refer to the standard 3 x 12 layout
+/// (row r holds 3r+1, 3r+2, 3r+3; column c holds c, c+3, ..., c+33) and 37 stands for 00.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum BetType {
     Red,
@@ -161,7 +1039,178 @@ pub enum BetType {
     Even,
     High,
     Low,
-    Number,
+    Number(u8),         // straight up on 0-36 (or 00), pays 35:1
+    Split([u8; 2]),     // two adjacent numbers, pays 17:1
+    Street(u8),         // first number of a row (1, 4, ..., 34), pays 11:1
+    Corner(u8),         // top-left number of a 2x2 block, pays 8:1
+    SixLine(u8),        // first number of two adjacent rows (1, 4, ..., 31), pays 5:1
+    Dozen(u8),          // 1 = 1-12, 2 = 13-24, 3 = 25-36, pays 2:1
+    Column(u8),         // 1, 2 or 3, pays 2:1
+    Basket,             // 0-1-2-3 (top line), single-zero only, pays 8:1
+    ZeroSplit([u8; 2]), // a zero pocket and its neighbour on the layout, pays 17:1
+    FiveNumber,         // 0-00-1-2-3, double-zero only, pays 6:1
+}
+
+impl BetType {
+    pub const LEN: usize = 1 + 2; // tag + largest payload
+
+    /// Rejects selections that do not form a valid layout on `variant`'s table
+    pub fn validate(&self, variant: WheelVariant) -> Result<()> {
+        let double_zero = variant == WheelVariant::DoubleZero;
+        match *self {
+            BetType::Red | BetType::Black | BetType::Odd | BetType::Even | BetType::High | BetType::Low => {}
+            BetType::Basket => require!(!double_zero, CustomError::BetNotOnWheel),
+            BetType::FiveNumber => require!(double_zero, CustomError::BetNotOnWheel),
+            BetType::Number(n) => {
+                require!(n <= 36 || (double_zero && n == DOUBLE_ZERO), CustomError::InvalidBetValue)
+            }
+            BetType::Split([a, b]) => {
+                let (lo, hi) = (a.min(b), a.max(b));
+                require!(lo >= 1 && hi <= 36, CustomError::InvalidSplit);
+                let side_by_side = hi == lo + 1 && (lo - 1) % 3 != 2;
+                let stacked = hi == lo + 3;
+                require!(side_by_side || stacked, CustomError::InvalidSplit);
+            }
+            BetType::Street(n) => {
+                require!((1..=34).contains(&n) && (n - 1) % 3 == 0, CustomError::InvalidStreet)
+            }
+            BetType::Corner(n) => {
+                require!((1..=32).contains(&n) && (n - 1) % 3 != 2, CustomError::InvalidCorner)
+            }
+            BetType::SixLine(n) => {
+                require!((1..=31).contains(&n) && (n - 1) % 3 == 0, CustomError::InvalidSixLine)
+            }
+            BetType::Dozen(d) => require!((1..=3).contains(&d), CustomError::InvalidDozen),
+            BetType::Column(c) => require!((1..=3).contains(&c), CustomError::InvalidColumn),
+            BetType::ZeroSplit([a, b]) => {
+                let pair = (a.min(b), a.max(b));
+                // Single zero sits above 1-2-3; on the American layout 0 sits above 1-2,
+                // 00 above 2-3, and the two zeros can be split with each other.
+                let adjacent = if double_zero {
+                    matches!(pair, (0, 1) | (0, 2) | (2, DOUBLE_ZERO) | (3, DOUBLE_ZERO) | (0, DOUBLE_ZERO))
+                } else {
+                    matches!(pair, (0, 1) | (0, 2) | (0, 3))
+                };
+                require!(adjacent, CustomError::InvalidZeroSplit);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the bet wins when the ball lands on `pocket`
+    pub fn covers(&self, pocket: u8) -> bool {
+        match *self {
+            BetType::Red => is_red(pocket),
+            BetType::Black => is_black(pocket),
+            BetType::Odd => !is_zero(pocket) && pocket & 1 == 1,
+            BetType::Even => !is_zero(pocket) && pocket & 1 == 0,
+            BetType::High => (19..=36).contains(&pocket),
+            BetType::Low => (1..=18).contains(&pocket),
+            BetType::Number(n) => pocket == n,
+            BetType::Split([a, b]) | BetType::ZeroSplit([a, b]) => pocket == a || pocket == b,
+            BetType::Street(n) => (n..n + 3).contains(&pocket),
+            BetType::Corner(n) => [n, n + 1, n + 3, n + 4].contains(&pocket),
+            BetType::SixLine(n) => (n..n + 6).contains(&pocket),
+            BetType::Dozen(d) => !is_zero(pocket) && (pocket - 1) / 12 + 1 == d,
+            BetType::Column(c) => !is_zero(pocket) && (pocket - 1) % 3 + 1 == c,
+            BetType::Basket => pocket <= 3,
+            BetType::FiveNumber => pocket <= 3 || pocket == DOUBLE_ZERO,
+        }
+    }
+
+    /// Red/black, odd/even and high/low: the bets La Partage and En Prison apply to
+    pub fn is_even_money(&self) -> bool {
+        matches!(
+            self,
+            BetType::Red | BetType::Black | BetType::Odd | BetType::Even | BetType::High | BetType::Low
+        )
+    }
+
+    /// Total returned to the player on a win (stake included)
+    pub fn payout_multiplier(&self) -> u64 {
+        match self {
+            BetType::Red | BetType::Black | BetType::Odd | BetType::Even | BetType::High | BetType::Low => 2,
+            BetType::Dozen(_) | BetType::Column(_) => 3,
+            BetType::SixLine(_) => 6,
+            BetType::FiveNumber => 7,
+            BetType::Corner(_) | BetType::Basket => 9,
+            BetType::Street(_) => 12,
+            BetType::Split(_) | BetType::ZeroSplit(_) => 18,
+            BetType::Number(_) => 36,
+        }
+    }
+}
+
+/// Player-owned responsible gaming limits. The rules are shared by every program in
+/// `casino_common::limits`; this account only ties them to the player.
+#[account]
+pub struct PlayerLimits {
+    pub player: Pubkey,
+    pub limits: LimitState,
+    pub bump: u8,
+}
+
+impl PlayerLimits {
+    pub const LEN: usize = 32 + LimitState::LEN + 1;
+
+    /// Fills in the owner of a freshly created account
+    fn init_if_new(&mut self, player: Pubkey, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.bump = bump;
+        }
+    }
+}
+
+impl From<LimitViolation> for CustomError {
+    fn from(violation: LimitViolation) -> Self {
+        match violation {
+            LimitViolation::SelfExcluded => CustomError::SelfExcluded,
+            LimitViolation::CooldownActive => CustomError::CooldownActive,
+            LimitViolation::AboveBetLimit => CustomError::AboveBetLimit,
+            LimitViolation::LossLimitReached => CustomError::LossLimitReached,
+            LimitViolation::CurrencyNotLimited => CustomError::CurrencyNotLimited,
+            LimitViolation::InvalidSettings => CustomError::InvalidLimits,
+            LimitViolation::Overflow => CustomError::Overflow,
+        }
+    }
+}
+
+#[event]
+pub struct BetPlaced {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub house: Pubkey,
+    pub mint: Pubkey, // default for SOL
+    pub bets: Vec<BetOutcome>, // includes bets carried over from prison
+    pub total_wagered: u64,
+    pub max_payout: u64,
+    pub house_commitment: [u8; 32],
+    pub bet_slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetSettled {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub house: Pubkey,
+    pub mint: Pubkey, // default for SOL
+    pub bets: Vec<BetOutcome>,
+    pub total_wagered: u64,
+    pub spin_result: u8,
+    pub payout: u64,
+    pub imprisoned: u64, // stakes held over for the next spin
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetRefunded {
+    pub game: Pubkey,
+    pub player: Pubkey,
+    pub mint: Pubkey, // default for SOL
+    pub refund: u64,
+    pub timestamp: i64,
 }
 
 #[error_code]
@@ -170,8 +1219,237 @@ pub enum CustomError {
     InvalidBetAmount,
     #[msg("Invalid bet value")]
     InvalidBetValue,
+    #[msg("Split must cover two adjacent numbers")]
+    InvalidSplit,
+    #[msg("Street must start on the first number of a row")]
+    InvalidStreet,
+    #[msg("Corner must start on the top-left number of a 2x2 block")]
+    InvalidCorner,
+    #[msg("Six line must start on the first number of a row")]
+    InvalidSixLine,
+    #[msg("Dozen must be 1, 2 or 3")]
+    InvalidDozen,
+    #[msg("Column must be 1, 2 or 3")]
+    InvalidColumn,
+    #[msg("Zero split must pair a zero pocket with an adjacent number")]
+    InvalidZeroSplit,
+    #[msg("Bet is not available on this wheel variant")]
+    BetNotOnWheel,
     #[msg("Math overflow")]
     Overflow,
+    #[msg("Invalid number of bets")]
+    InvalidBetCount,
+    #[msg("Game is currently paused")]
+    GamePaused,
+    #[msg("Bet amount is too low")]
+    BetTooLow,
+    #[msg("Bet amount is too high")]
+    BetTooHigh,
+    #[msg("Worst-case payout exceeds the table exposure limit")]
+    ExposureTooHigh,
+    #[msg("Invalid bet range. Max bet must be >= min bet")]
+    InvalidBetRange,
+    #[msg("Previous spin on this game account is not settled")]
+    BetNotSettled,
+    #[msg("Imprisoned bets must resolve before the game can close")]
+    ImprisonedBetsPending,
+    #[msg("Bet already settled")]
+    BetAlreadySettled,
+    #[msg("House seed does not match commitment")]
+    InvalidHouseSeed,
+    #[msg("Slot hash for the bet slot is not available")]
+    SlotHashUnavailable,
+    #[msg("Settle window is still open")]
+    SettleWindowOpen,
+    #[msg("Insufficient house funds for payout")]
+    InsufficientHouseFunds,
+    #[msg("Invalid amount specified")]
+    InvalidAmount,
+    #[msg("Token accounts are required for games in an SPL mint")]
+    TokenAccountsMissing,
+    #[msg("Token account does not match the token house")]
+    InvalidTokenAccount,
+    #[msg("Imprisoned bets must ride in the same mint")]
+    MintMismatch,
+    #[msg("Player is self-excluded")]
+    SelfExcluded,
+    #[msg("Player's session cooldown has not passed")]
+    CooldownActive,
+    #[msg("Bet is above the player's max bet")]
+    AboveBetLimit,
+    #[msg("Bet would exceed the player's loss limit")]
+    LossLimitReached,
+    #[msg("Mints with a transfer fee are not supported")]
+    TransferFeeMint,
+    #[msg("Player's limits do not cover this currency")]
+    CurrencyNotLimited,
+    #[msg("Invalid limits. Up to 4 currencies, each listed once")]
+    InvalidLimits,
+}
+
+/// Validated token accounts for one game, borrowed out of `TokenAccounts`
+struct TokenLeg<'a, 'info> {
+    house: &'a mut Account<'info, TokenHouse>,
+    vault: &'a InterfaceAccount<'info, TokenAccount>,
+    player_account: &'a InterfaceAccount<'info, TokenAccount>,
+    mint: &'a InterfaceAccount<'info, Mint>,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'info> TokenAccounts<'info> {
+    /// Mint of the passed token accounts, or the default pubkey (SOL) when none are passed
+    fn mint_key(&self) -> Pubkey {
+        self.mint.as_ref().map_or(Pubkey::default(), |mint| mint.key())
+    }
+
+    /// Checks that every account is present and belongs to `mint`'s token house and `player`
+    fn leg(&mut self, mint: Pubkey, player: Pubkey) -> Result<TokenLeg<'_, 'info>> {
+        let (Some(house), Some(vault), Some(player_account), Some(mint_account), Some(token_program)) = (
+            self.token_house.as_mut(),
+            self.house_token_account.as_ref(),
+            self.player_token_account.as_ref(),
+            self.mint.as_ref(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(CustomError::TokenAccountsMissing);
+        };
+        require_keys_eq!(mint_account.key(), mint, CustomError::InvalidTokenAccount);
+        require_keys_eq!(house.mint, mint, CustomError::InvalidTokenAccount);
+        require_keys_eq!(house.vault, vault.key(), CustomError::InvalidTokenAccount);
+        require_keys_eq!(player_account.mint, mint, CustomError::InvalidTokenAccount);
+        require_keys_eq!(player_account.owner, player, CustomError::InvalidTokenAccount);
+        Ok(TokenLeg {
+            house,
+            vault,
+            player_account,
+            mint: mint_account,
+            token_program,
+        })
+    }
+}
+
+impl<'info> TokenLeg<'_, 'info> {
+    /// Moves `amount` from the player's token account into the vault
+    fn deposit(&self, player: &Signer<'info>, amount: u64) -> Result<()> {
+        token_interface::transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.player_account.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: player.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+
+    /// Pays `amount` from the vault to the player's token account, signed by the token house
+    fn pay(&self, amount: u64) -> Result<()> {
+        let mint = self.house.mint;
+        let seeds: &[&[u8]] = &[b"token_house", mint.as_ref(), &[self.house.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.player_account.to_account_info(),
+                    authority: self.house.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+}
+
+/// How `bet` resolves when the ball lands on `pocket`
+fn resolve(bet: &BetOutcome, pocket: u8, rule: EvenMoneyRule) -> Result<BetOutcome> {
+    let mut outcome = BetOutcome {
+        won: false,
+        payout: 0,
+        imprisoned: false,
+        ..*bet
+    };
+    if bet.imprisoned {
+        // An imprisoned bet only gets its stake back; landing on zero again loses it
+        outcome.won = bet.bet_type.covers(pocket);
+        outcome.payout = if outcome.won { bet.amount } else { 0 };
+    } else if bet.bet_type.covers(pocket) {
+        outcome.won = true;
+        outcome.payout = bet
+            .amount
+            .checked_mul(bet.bet_type.payout_multiplier())
+            .ok_or(CustomError::Overflow)?;
+    } else if is_zero(pocket) && bet.bet_type.is_even_money() {
+        match rule {
+            EvenMoneyRule::Standard => {}
+            EvenMoneyRule::LaPartage => outcome.payout = bet.amount / 2,
+            EvenMoneyRule::EnPrison => outcome.imprisoned = true,
+        }
+    }
+    Ok(outcome)
+}
+
+/// Worst case the vault can owe on one spin: the most any single pocket pays out,
+/// counting stakes that would go to prison as owed.
+fn max_payout(variant: WheelVariant, rule: EvenMoneyRule, bets: &[BetOutcome]) -> Result<u64> {
+    let mut worst: u64 = 0;
+    for pocket in 0..variant.pockets() {
+        let mut owed: u64 = 0;
+        for bet in bets.iter() {
+            let outcome = resolve(bet, pocket, rule)?;
+            let held = if outcome.imprisoned { outcome.amount } else { 0 };
+            owed = owed
+                .checked_add(outcome.payout)
+                .and_then(|owed| owed.checked_add(held))
+                .ok_or(CustomError::Overflow)?;
+        }
+        worst = worst.max(owed);
+    }
+    Ok(worst)
+}
+
+/// Mixes both seeds and the bet slot hash into a pocket (37 is 00 on double-zero wheels).
+/// Public so clients can recompute a settled spin from the stored game account.
+pub fn derive_spin_result(
+    variant: WheelVariant,
+    house_seed: &[u8; 32],
+    player_seed: &[u8; 32],
+    slot_hash: &[u8; 32],
+) -> u8 {
+    let mixed = hashv(&[&house_seed[..], &player_seed[..], &slot_hash[..]]).to_bytes();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&mixed[..8]);
+    (u64::from_le_bytes(head) % variant.pockets() as u64) as u8
+}
+
+/// Looks up the hash of `slot` in raw SlotHashes sysvar data.
+/// Layout: u64 entry count, then (u64 slot, [u8; 32] hash) entries, newest first.
+fn find_slot_hash(data: &[u8], slot: u64) -> Option<[u8; 32]> {
+    let mut count = [0u8; 8];
+    count.copy_from_slice(data.get(..8)?);
+    let entries = data.get(8..)?;
+    entries.chunks_exact(40).take(u64::from_le_bytes(count) as usize).find_map(|entry| {
+        let mut entry_slot = [0u8; 8];
+        entry_slot.copy_from_slice(&entry[..8]);
+        if u64::from_le_bytes(entry_slot) == slot {
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&entry[8..]);
+            Some(hash)
+        } else {
+            None
+        }
+    })
+}
+
+/// 0 and 00 lose every outside bet
+fn is_zero(pocket: u8) -> bool {
+    pocket == 0 || pocket == DOUBLE_ZERO
 }
 
 /// Helper functions for color mapping
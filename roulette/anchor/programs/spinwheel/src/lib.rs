@@ -1,19 +1,35 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv; // commit-reveal hashing
 use anchor_lang::system_program;
-use arrayref::array_ref;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use casino_common::limits::{LimitSettings, LimitState, LimitViolation, SECONDS_PER_DAY};
+use casino_common::token::has_transfer_fee;
 
 declare_id!("AbzPJiJqYBQNYrqgi2bfCiT19LB8BsesDrE5mWDbaePR");
 
+const FULFIL_WINDOW_SLOTS: u64 = 512; // SlotHashes only keeps the last 512 slots
+const MIN_SEGMENTS: usize = 2;
+const MAX_SEGMENTS: usize = 54; // largest Big Six / Money Wheel layout
+const DEFAULT_SEGMENTS: usize = 8;
+const DEFAULT_MULTIPLIER: u16 = 8; // 7x profit + stake back on an 8-segment wheel
+const MAX_ADMINS: usize = 10; // approvals are a u16 bitmap over the admin list
+const WITHDRAWAL_WINDOW_SECS: i64 = SECONDS_PER_DAY; // unqueued withdrawals are capped per window
+const MAX_STATS_CURRENCIES: usize = 4; // currencies a player's stats are kept in, per table
+
 #[program]
 pub mod spinwheel {
     use super::*;
 
-    /// Initializes the spin wheel game with given house edge
-    pub fn initialize(ctx: Context<Initialize>, house_edge: u8) -> Result<()> {
+    /// Opens a new table under `table_id` with the given house edge.
+    /// The signer becomes the table's authority and house; each table has its own vault and wheel.
+    /// Tables are keyed by their creator and id, so nobody can claim an id in another operator's namespace.
+    pub fn initialize(ctx: Context<Initialize>, table_id: u64, house_edge: u8) -> Result<()> {
         let game_state = &mut ctx.accounts.game_state;
         
         require!(house_edge <= 20, SpinWheelError::InvalidHouseEdge);
         
+        game_state.table_id = table_id;
+        game_state.creator = ctx.accounts.authority.key();
         game_state.authority = ctx.accounts.authority.key();
         game_state.house_edge = house_edge;
         game_state.total_games = 0;
@@ -22,108 +38,269 @@ pub mod spinwheel {
         game_state.is_paused = false;
         game_state.min_bet = 1_000_000; // 0.001 SOL in lamports
         game_state.max_bet = 1_000_000_000; // 1 SOL in lamports
+        game_state.reserved = 0;
+        game_state.pending_spins = 0;
+        game_state.pending_authority = Pubkey::default();
+        game_state.multisig_enabled = false;
+        game_state.withdrawal_threshold = u64::MAX; // no timelock until configured
+        game_state.withdrawal_delay = 0;
+        game_state.queued_withdrawal = 0;
+        game_state.withdrawal_unlocks_at = 0;
+        game_state.pending_withdrawal_threshold = 0;
+        game_state.pending_withdrawal_delay = 0;
+        game_state.timelock_change_at = 0;
+        game_state.withdrawal_window = 0;
+        game_state.window_withdrawn = 0;
         game_state.bump = ctx.bumps.game_state; // Store bump for PDA validation
         
-        msg!("Spin wheel initialized with house edge: {}%", house_edge);
+        // Start with the classic wheel: 8 equal segments paying 8x
+        let wheel_config = &mut ctx.accounts.wheel_config;
+        wheel_config.segments = vec![
+            Segment {
+                weight: 1,
+                multiplier: DEFAULT_MULTIPLIER,
+            };
+            DEFAULT_SEGMENTS
+        ];
+        wheel_config.total_weight = DEFAULT_SEGMENTS as u32;
+        wheel_config.max_multiplier = DEFAULT_MULTIPLIER;
+        wheel_config.bump = ctx.bumps.wheel_config;
+        
+        msg!("Spin wheel table {} initialized with house edge: {}%", table_id, house_edge);
         Ok(())
     }
 
-    /// Executes a spin with a bet and prediction
-    pub fn spin(ctx: Context<Spin>, bet_amount: u64, prediction: u8) -> Result<()> {
-        let game_state = &mut ctx.accounts.game_state;
+    /// Replaces the wheel layout: segment count, weights and multipliers.
+    /// Only allowed while no spin is pending, so every pending spin settles on the wheel it was placed on.
+    pub fn configure_wheel(ctx: Context<ConfigureWheel>, segments: Vec<Segment>) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        require!(!game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        apply_wheel(game_state, &mut ctx.accounts.wheel_config, segments)
+    }
+
+    /// Phase 1: records a pending spin with a bet and prediction.
+    /// - house co-signs and supplies `house_commitment` = hash(house_seed)
+    /// - player supplies their own `player_seed`
+    /// - the stake moves into the house vault, which reserves the winning payout
+    /// - the wheel is drawn later in `fulfil_spin` from both seeds and the hash of the spin slot
+    /// Passing the `token` accounts stakes the bet in that mint's token vault instead of SOL.
+    pub fn spin(
+        ctx: Context<Spin>,
+        bet_amount: u64,
+        prediction: u8,
+        house_commitment: [u8; 32],
+        player_seed: [u8; 32],
+    ) -> Result<()> {
+        let game_state = &ctx.accounts.game_state;
+        let wheel_config = &ctx.accounts.wheel_config;
+        let mint = ctx.accounts.token.mint_key();
+        let player = ctx.accounts.player.key();
+        let table = game_state.key();
+        let (min_bet, max_bet) = if mint == Pubkey::default() {
+            (game_state.min_bet, game_state.max_bet)
+        } else {
+            let leg = ctx.accounts.token.leg(table, mint, player)?;
+            (leg.table.min_bet, leg.table.max_bet)
+        };
         
         // Validation checks
         require!(!game_state.is_paused, SpinWheelError::GamePaused);
-        require!(bet_amount >= game_state.min_bet, SpinWheelError::BetTooLow);
-        require!(bet_amount <= game_state.max_bet, SpinWheelError::BetTooHigh);
-        require!(prediction < 8, SpinWheelError::InvalidPrediction); // 8 segments (0-7)
-        
-        // Ensure house vault can cover the worst-case payout after receiving the bet
-        let min_rent_balance = Rent::get()?.minimum_balance(8);
-        let base_payout = bet_amount
-            .checked_mul(8)
-            .ok_or(SpinWheelError::ArithmeticOverflow)?;
-        let house_cut = base_payout
-            .checked_mul(game_state.house_edge as u64)
-            .ok_or(SpinWheelError::ArithmeticOverflow)?
-            / 100;
-        let max_possible_payout = base_payout
-            .checked_sub(house_cut)
-            .ok_or(SpinWheelError::ArithmeticOverflow)?;
-        let projected_vault_balance = ctx
-            .accounts
-            .house_vault
-            .lamports()
-            .saturating_add(bet_amount);
-        require!(
-            projected_vault_balance.saturating_sub(max_possible_payout) >= min_rent_balance,
-            SpinWheelError::InsufficientHouseFunds
-        );
-
-        // Transfer bet to house vault
-        system_program::transfer(
-            CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                system_program::Transfer {
-                    from: ctx.accounts.player.to_account_info(),
-                    to: ctx.accounts.house_vault.to_account_info(),
-                },
-            ),
+        require!(bet_amount >= min_bet, SpinWheelError::BetTooLow);
+        require!(bet_amount <= max_bet, SpinWheelError::BetTooHigh);
+        
+        // The player's own limits; stakes count as lost in their currency until paid back
+        let player_limits = &mut ctx.accounts.player_limits;
+        player_limits.init_if_new(player, ctx.bumps.player_limits);
+        player_limits.limits.record_play(
+            Clock::get()?.unix_timestamp,
+            mint,
             bet_amount,
-        )?;
+            bet_amount,
+        ).map_err(SpinWheelError::from)?;
+        
+        let segment = wheel_config
+            .segments
+            .get(prediction as usize)
+            .ok_or(SpinWheelError::InvalidPrediction)?;
+        // Non-paying segments can't be bet on
+        require!(segment.multiplier > 0, SpinWheelError::InvalidPrediction);
+        let payout_if_won = win_payout(bet_amount, segment.multiplier, game_state.house_edge)?;
         
-        // Generate pseudo-random number with improved seed
-        let recent_slothashes = &ctx.accounts.recent_slothashes;
-        let data = recent_slothashes.data.borrow();
-        let most_recent = array_ref![data, 12, 8];
+        // Ensure the vault can cover the best-paying segment after receiving the bet,
+        // on top of what is already reserved for other pending spins
+        let max_possible_payout =
+            win_payout(bet_amount, wheel_config.max_multiplier, game_state.house_edge)?;
+        if mint == Pubkey::default() {
+            let min_rent_balance = Rent::get()?.minimum_balance(8);
+            let required = game_state
+                .reserved
+                .checked_add(max_possible_payout)
+                .ok_or(SpinWheelError::ArithmeticOverflow)?;
+            let projected_vault_balance = ctx
+                .accounts
+                .house_vault
+                .lamports()
+                .saturating_add(bet_amount);
+            require!(
+                projected_vault_balance.saturating_sub(required) >= min_rent_balance,
+                SpinWheelError::InsufficientHouseFunds
+            );
+
+            // Transfer bet to house vault
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.player.to_account_info(),
+                        to: ctx.accounts.house_vault.to_account_info(),
+                    },
+                ),
+                bet_amount,
+            )?;
+            let game_state = &mut ctx.accounts.game_state;
+            game_state.reserved = game_state
+                .reserved
+                .checked_add(payout_if_won)
+                .ok_or(SpinWheelError::ArithmeticOverflow)?;
+        } else {
+            let accounts = &mut *ctx.accounts;
+            let leg = accounts.token.leg(table, mint, player)?;
+            let required = leg
+                .table
+                .reserved
+                .checked_add(max_possible_payout)
+                .ok_or(SpinWheelError::ArithmeticOverflow)?;
+            require!(
+                leg.vault.amount.saturating_add(bet_amount) >= required,
+                SpinWheelError::InsufficientHouseFunds
+            );
+
+            // Transfer bet to the table's token vault
+            leg.deposit(&accounts.player, bet_amount)?;
+            leg.table.reserved = leg
+                .table
+                .reserved
+                .checked_add(payout_if_won)
+                .ok_or(SpinWheelError::ArithmeticOverflow)?;
+        }
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.pending_spins = game_state.pending_spins.saturating_add(1);
         
         let clock = Clock::get()?;
-        let seed = u64::from_le_bytes(*most_recent)
-            .wrapping_add(clock.slot)
-            .wrapping_add(clock.unix_timestamp as u64)
-            .wrapping_add(bet_amount)
-            .wrapping_add(ctx.accounts.player.key().to_bytes()[0] as u64); // Add player pubkey byte
+        let pending_spin = &mut ctx.accounts.pending_spin;
+        pending_spin.player = player;
+        pending_spin.mint = mint;
+        pending_spin.bet_amount = bet_amount;
+        pending_spin.prediction = prediction;
+        pending_spin.payout_if_won = payout_if_won;
+        pending_spin.house_commitment = house_commitment;
+        pending_spin.player_seed = player_seed;
+        pending_spin.spin_slot = clock.slot;
+        pending_spin.bump = ctx.bumps.pending_spin;
+        
+        // Created on the player's first spin at this table; the outcome is tallied in `fulfil_spin`
+        let player_stats = &mut ctx.accounts.player_stats;
+        if player_stats.player == Pubkey::default() {
+            player_stats.player = player;
+            player_stats.game_state = table;
+            player_stats.bump = ctx.bumps.player_stats;
+        }
+        player_stats.last_spin_timestamp = clock.unix_timestamp;
+        
+        msg!(
+            "Spin requested: Player bet {} on {} at slot {}",
+            bet_amount,
+            prediction,
+            clock.slot
+        );
         
-        let result = (seed % 8) as u8;
+        Ok(())
+    }
+
+    /// Phase 2: house reveals its seed and the pending spin is settled.
+    /// roll = u64_le(hash(house_seed || player_seed || slot_hash(spin_slot))[..8]) % total_weight,
+    /// and the result is the segment whose cumulative weight range contains the roll
+    pub fn fulfil_spin(ctx: Context<FulfilSpin>, house_seed: [u8; 32]) -> Result<()> {
+        let pending_spin = &ctx.accounts.pending_spin;
+        require!(
+            hashv(&[&house_seed[..]]).to_bytes() == pending_spin.house_commitment,
+            SpinWheelError::InvalidHouseSeed
+        );
+
+        let clock = Clock::get()?;
+        require!(clock.slot > pending_spin.spin_slot, SpinWheelError::SlotHashUnavailable);
+        let slot_hash = {
+            let data = ctx.accounts.recent_slothashes.try_borrow_data()?;
+            find_slot_hash(&data, pending_spin.spin_slot)
+                .ok_or(SpinWheelError::SlotHashUnavailable)?
+        };
+
+        let result = derive_spin_result(
+            &ctx.accounts.wheel_config,
+            &house_seed,
+            &pending_spin.player_seed,
+            &slot_hash,
+        );
+        let bet_amount = pending_spin.bet_amount;
+        let prediction = pending_spin.prediction;
         
         // Calculate payout
         let (payout, is_winner) = if result == prediction {
-            // Pay 8x on win so the player effectively receives 7x profit + original stake back
-            let base_payout = bet_amount.checked_mul(8).ok_or(SpinWheelError::ArithmeticOverflow)?;
-            let house_cut = base_payout
-                .checked_mul(game_state.house_edge as u64)
-                .ok_or(SpinWheelError::ArithmeticOverflow)?
-                / 100;
-            let final_payout = base_payout
-                .checked_sub(house_cut)
-                .ok_or(SpinWheelError::ArithmeticOverflow)?;
-            (final_payout, true)
+            // Pay the predicted segment's multiplier (stake included), fixed at spin time
+            (pending_spin.payout_if_won, true)
         } else {
             (0, false)
         };
         
-        // Update game statistics
+        // Release the reservation and update game statistics; totals are per currency,
+        // so token spins are tallied on their token table
+        let game_state = &mut ctx.accounts.game_state;
         game_state.total_games = game_state.total_games.saturating_add(1);
-        game_state.total_wagered = game_state.total_wagered.saturating_add(bet_amount);
-        
-        // Pay out winnings if applicable
-        if is_winner && payout > 0 {
-            let house_balance = ctx.accounts.house_vault.lamports();
-            let min_rent_balance = Rent::get()?.minimum_balance(8);
-            require!(
-                house_balance.saturating_sub(payout) >= min_rent_balance,
-                SpinWheelError::InsufficientHouseFunds
-            );
+        game_state.pending_spins = game_state.pending_spins.saturating_sub(1);
+        if pending_spin.mint == Pubkey::default() {
+            game_state.reserved = game_state.reserved.saturating_sub(pending_spin.payout_if_won);
+            game_state.total_wagered = game_state.total_wagered.saturating_add(bet_amount);
             
-            **ctx.accounts.house_vault.to_account_info().try_borrow_mut_lamports()? -= payout;
-            **ctx.accounts.player.to_account_info().try_borrow_mut_lamports()? += payout;
-            
-            game_state.total_paid_out = game_state.total_paid_out.saturating_add(payout);
+            // Pay out winnings if applicable (reserved at spin time, so the vault can cover it)
+            if is_winner && payout > 0 {
+                **ctx.accounts.house_vault.to_account_info().try_borrow_mut_lamports()? -= payout;
+                **ctx.accounts.player.to_account_info().try_borrow_mut_lamports()? += payout;
+                
+                game_state.total_paid_out = game_state.total_paid_out.saturating_add(payout);
+            }
+        } else {
+            let leg = ctx
+                .accounts
+                .token
+                .leg(game_state.key(), pending_spin.mint, pending_spin.player)?;
+            leg.table.reserved = leg.table.reserved.saturating_sub(pending_spin.payout_if_won);
+            leg.table.total_wagered = leg.table.total_wagered.saturating_add(bet_amount);
+            if is_winner && payout > 0 {
+                leg.pay(payout)?;
+                leg.table.total_paid_out = leg.table.total_paid_out.saturating_add(payout);
+            }
+        }
+        ctx.accounts.player_limits.limits.record_payout(clock.unix_timestamp, pending_spin.mint, payout);
+        
+        // Tallied per currency, since amounts in different mints don't add up
+        if let Some(stats) = ctx.accounts.player_stats.stats_in(pending_spin.mint) {
+            stats.games_played = stats.games_played.saturating_add(1);
+            stats.total_wagered = stats.total_wagered.saturating_add(bet_amount);
+            stats.total_won = stats.total_won.saturating_add(payout);
+            stats.biggest_win = stats.biggest_win.max(payout);
+            stats.current_streak = match (is_winner, stats.current_streak) {
+                (true, streak) if streak > 0 => streak.saturating_add(1),
+                (true, _) => 1,
+                (false, streak) if streak < 0 => streak.saturating_sub(1),
+                (false, _) => -1,
+            };
         }
         
         // Emit game result event
         emit!(SpinResult {
+            table_id: game_state.table_id,
             player: ctx.accounts.player.key(),
+            mint: pending_spin.mint,
             bet_amount,
             prediction,
             result,
@@ -131,6 +308,10 @@ pub mod spinwheel {
             is_winner,
             timestamp: clock.unix_timestamp,
             house_edge: game_state.house_edge, // Added house edge to event
+            multiplier: ctx.accounts.wheel_config.segments[prediction as usize].multiplier,
+            house_seed,
+            player_seed: pending_spin.player_seed,
+            spin_slot: pending_spin.spin_slot,
         });
         
         msg!(
@@ -139,12 +320,58 @@ pub mod spinwheel {
             prediction,
             result,
             if is_winner { 
-                format!("Won {} lamports!", payout) 
+                format!("Won {}!", payout) 
             } else { 
                 "Lost!".to_string() 
             }
         );
         
+        // Anchor will close `pending_spin` to `player` (close = player), returning rent.
+        Ok(())
+    }
+
+    /// If the house never reveals its seed while the spin slot hash is still available,
+    /// the player is paid the reserved winning payout (never less than the stake).
+    /// Once the spin slot passes the house can work out the result, so letting a spin
+    /// expire must cost it as much as a win.
+    /// Anyone can crank it once the window has passed, so an abandoned spin cannot keep
+    /// `pending_spins` above zero and lock the wheel layout.
+    pub fn refund_spin(ctx: Context<RefundSpin>) -> Result<()> {
+        let pending_spin = &ctx.accounts.pending_spin;
+        let clock = Clock::get()?;
+        require!(
+            clock.slot > pending_spin.spin_slot.saturating_add(FULFIL_WINDOW_SLOTS),
+            SpinWheelError::FulfilWindowOpen
+        );
+
+        // Settled as a forfeited spin, so it counts towards the table's totals
+        let game_state = &mut ctx.accounts.game_state;
+        game_state.total_games = game_state.total_games.saturating_add(1);
+        game_state.pending_spins = game_state.pending_spins.saturating_sub(1);
+
+        let bet_amount = pending_spin.bet_amount;
+        let refund = bet_amount.max(pending_spin.payout_if_won);
+        if pending_spin.mint == Pubkey::default() {
+            game_state.reserved = game_state.reserved.saturating_sub(pending_spin.payout_if_won);
+            game_state.total_wagered = game_state.total_wagered.saturating_add(bet_amount);
+            game_state.total_paid_out = game_state.total_paid_out.saturating_add(refund);
+            **ctx.accounts.house_vault.to_account_info().try_borrow_mut_lamports()? -= refund;
+            **ctx.accounts.player.to_account_info().try_borrow_mut_lamports()? += refund;
+        } else {
+            let leg = ctx
+                .accounts
+                .token
+                .leg(game_state.key(), pending_spin.mint, pending_spin.player)?;
+            leg.table.reserved = leg.table.reserved.saturating_sub(pending_spin.payout_if_won);
+            leg.table.total_wagered = leg.table.total_wagered.saturating_add(bet_amount);
+            leg.table.total_paid_out = leg.table.total_paid_out.saturating_add(refund);
+            leg.pay(refund)?;
+        }
+        ctx.accounts.player_limits.limits.record_payout(clock.unix_timestamp, pending_spin.mint, refund);
+
+        msg!("Refunded {} for an unfulfilled spin", refund);
+        
+        // Anchor will close `pending_spin` to `player` (close = player), returning rent.
         Ok(())
     }
     
@@ -157,49 +384,23 @@ pub mod spinwheel {
         is_paused: Option<bool>,
     ) -> Result<()> {
         let game_state = &mut ctx.accounts.game_state;
-        
-        if let Some(edge) = house_edge {
-            require!(edge <= 20, SpinWheelError::InvalidHouseEdge);
-            game_state.house_edge = edge;
-        }
-        
-        // Apply min/max updates atomically to avoid invalid states
-        if min_bet.is_some() || max_bet.is_some() {
-            let new_min = min_bet.unwrap_or(game_state.min_bet);
-            let new_max = max_bet.unwrap_or(game_state.max_bet);
-            require!(new_min > 0, SpinWheelError::InvalidBetRange);
-            require!(new_max >= new_min, SpinWheelError::InvalidBetRange);
-            game_state.min_bet = new_min;
-            game_state.max_bet = new_max;
-        }
-        
-        if let Some(paused) = is_paused {
-            game_state.is_paused = paused;
-        }
-        
-        msg!("Settings updated: house_edge={:?}, min_bet={:?}, max_bet={:?}, is_paused={:?}",
-            house_edge, min_bet, max_bet, is_paused);
-        Ok(())
+        require!(!game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        apply_settings(game_state, house_edge, min_bet, max_bet, is_paused)
     }
     
-    /// Withdraws funds from house vault (only when paused)
+    /// Withdraws funds from house vault (only when paused, never below pending payouts).
+    /// Withdrawals that would take the window's total above the withdrawal threshold
+    /// must go through `queue_withdrawal` instead.
     pub fn withdraw_house_funds(ctx: Context<WithdrawHouseFunds>, amount: u64) -> Result<()> {
-        let game_state = &ctx.accounts.game_state;
-        require!(game_state.is_paused, SpinWheelError::GameNotPaused); // Added pause check
-        require!(amount > 0, SpinWheelError::InvalidAmount);
-        
-        let house_balance = ctx.accounts.house_vault.lamports();
-        let min_rent_balance = Rent::get()?.minimum_balance(8);
-        require!(
-            house_balance.saturating_sub(amount) >= min_rent_balance,
-            SpinWheelError::InsufficientHouseFunds
-        );
-        
-        **ctx.accounts.house_vault.to_account_info().try_borrow_mut_lamports()? -= amount;
-        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += amount;
-        
-        msg!("Withdrew {} lamports from house vault", amount);
-        Ok(())
+        let game_state = &mut ctx.accounts.game_state;
+        require!(!game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        record_unqueued_withdrawal(game_state, amount)?;
+        withdraw_lamports(
+            game_state,
+            &ctx.accounts.house_vault.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            amount,
+        )
     }
     
     /// Funds the house vault
@@ -220,15 +421,403 @@ pub mod spinwheel {
         msg!("Funded house vault with {} lamports", amount);
         Ok(())
     }
+    
+    /// Whitelists an SPL mint (Token or Token-2022) on this table with its own vault and limits.
+    /// Mints with transfer fees are not supported.
+    pub fn initialize_token_table(
+        ctx: Context<InitializeTokenTable>,
+        min_bet: u64,
+        max_bet: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        apply_new_token_table(
+            &mut ctx.accounts.token_table,
+            ctx.accounts.game_state.key(),
+            &ctx.accounts.mint,
+            ctx.accounts.token_vault.key(),
+            ctx.bumps.token_table,
+            min_bet,
+            max_bet,
+        )
+    }
+    
+    /// Updates a token table's bet limits, in the mint's base units
+    pub fn update_token_table(
+        ctx: Context<UpdateTokenTable>,
+        min_bet: Option<u64>,
+        max_bet: Option<u64>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        apply_token_table_limits(&mut ctx.accounts.token_table, min_bet, max_bet)
+    }
+    
+    /// Funds a token table's vault
+    pub fn fund_token_vault(ctx: Context<FundTokenVault>, amount: u64) -> Result<()> {
+        require!(amount > 0, SpinWheelError::InvalidAmount);
+        
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.funder_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.token_vault.to_account_info(),
+                    authority: ctx.accounts.funder.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+        
+        msg!("Funded token vault with {}", amount);
+        Ok(())
+    }
+    
+    /// Queues a token vault withdrawal behind the table's withdrawal delay.
+    /// Token withdrawals are always queued; one can be queued per token table at a time.
+    pub fn queue_token_withdrawal(ctx: Context<UpdateTokenTable>, amount: u64) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        require!(!game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        queue_token_vault_withdrawal(game_state, &mut ctx.accounts.token_table, amount)
+    }
+    
+    /// Pays out a token table's queued withdrawal to the authority once its delay has passed.
+    /// The game must be paused and pending payouts stay covered.
+    pub fn execute_token_withdrawal(ctx: Context<ExecuteTokenWithdrawal>) -> Result<()> {
+        let token_table = &mut ctx.accounts.token_table;
+        let amount = token_table.queued_withdrawal;
+        require!(amount > 0, SpinWheelError::NoQueuedWithdrawal);
+        require!(
+            Clock::get()?.unix_timestamp >= token_table.withdrawal_unlocks_at,
+            SpinWheelError::WithdrawalLocked
+        );
+        
+        withdraw_tokens(
+            &ctx.accounts.game_state,
+            token_table,
+            &ctx.accounts.token_vault,
+            &ctx.accounts.mint,
+            &ctx.accounts.authority_token_account,
+            &ctx.accounts.token_program,
+            amount,
+        )?;
+        token_table.queued_withdrawal = 0;
+        token_table.withdrawal_unlocks_at = 0;
+        
+        emit!(WithdrawalExecuted {
+            table_id: ctx.accounts.game_state.table_id,
+            mint: token_table.mint,
+            amount,
+        });
+        Ok(())
+    }
+    
+    /// Drops a token table's queued withdrawal; allowed under a multisig like `cancel_withdrawal`
+    pub fn cancel_token_withdrawal(ctx: Context<UpdateTokenTable>) -> Result<()> {
+        let token_table = &mut ctx.accounts.token_table;
+        let amount = token_table.queued_withdrawal;
+        require!(amount > 0, SpinWheelError::NoQueuedWithdrawal);
+        token_table.queued_withdrawal = 0;
+        token_table.withdrawal_unlocks_at = 0;
+        
+        emit!(WithdrawalCancelled {
+            table_id: ctx.accounts.game_state.table_id,
+            mint: token_table.mint,
+            amount,
+        });
+        Ok(())
+    }
+    
+    /// Sets the most the house vault can pay out per window without a timelock,
+    /// and how long queued withdrawals wait. Tightening applies at once; a higher threshold
+    /// or a shorter delay only applies after the delay in force.
+    pub fn configure_withdrawal_timelock(
+        ctx: Context<UpdateSettings>,
+        threshold: u64,
+        delay_seconds: i64,
+    ) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        require!(!game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        apply_withdrawal_timelock(game_state, threshold, delay_seconds)
+    }
+    
+    /// Queues a house vault withdrawal that becomes executable after the withdrawal delay.
+    /// One withdrawal can be queued per table at a time.
+    pub fn queue_withdrawal(ctx: Context<UpdateSettings>, amount: u64) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        require!(!game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        queue_house_withdrawal(game_state, amount)
+    }
+    
+    /// Pays out the queued withdrawal to the authority once its delay has passed.
+    /// The usual withdrawal rules apply at this point: the game must be paused and pending payouts stay covered.
+    pub fn execute_withdrawal(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        let amount = game_state.queued_withdrawal;
+        require!(amount > 0, SpinWheelError::NoQueuedWithdrawal);
+        require!(
+            Clock::get()?.unix_timestamp >= game_state.withdrawal_unlocks_at,
+            SpinWheelError::WithdrawalLocked
+        );
+        
+        withdraw_lamports(
+            game_state,
+            &ctx.accounts.house_vault.to_account_info(),
+            &ctx.accounts.authority.to_account_info(),
+            amount,
+        )?;
+        game_state.queued_withdrawal = 0;
+        game_state.withdrawal_unlocks_at = 0;
+        
+        emit!(WithdrawalExecuted {
+            table_id: game_state.table_id,
+            mint: Pubkey::default(),
+            amount,
+        });
+        Ok(())
+    }
+    
+    /// Drops the queued withdrawal. Allowed for the authority even under a multisig, since it only keeps funds in.
+    pub fn cancel_withdrawal(ctx: Context<UpdateSettings>) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        let amount = game_state.queued_withdrawal;
+        require!(amount > 0, SpinWheelError::NoQueuedWithdrawal);
+        game_state.queued_withdrawal = 0;
+        game_state.withdrawal_unlocks_at = 0;
+        
+        emit!(WithdrawalCancelled {
+            table_id: game_state.table_id,
+            mint: Pubkey::default(),
+            amount,
+        });
+        Ok(())
+    }
+    
+    /// Starts handing the table to `new_authority`, who must call `accept_authority` to take over.
+    /// Proposing the default pubkey cancels a pending transfer.
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        require!(!game_state.multisig_enabled, SpinWheelError::MultisigRequired);
+        game_state.pending_authority = new_authority;
+        
+        msg!("Authority transfer proposed to {}", new_authority);
+        Ok(())
+    }
+    
+    /// Completes a transfer started by `propose_authority`.
+    /// The authority is also the table's house, so spins committed by the old house can only be
+    /// refunded once their fulfilment window expires.
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let game_state = &mut ctx.accounts.game_state;
+        let previous_authority = game_state.authority;
+        game_state.authority = ctx.accounts.new_authority.key();
+        game_state.pending_authority = Pubkey::default();
+        
+        emit!(AuthorityTransferred {
+            table_id: game_state.table_id,
+            previous_authority,
+            new_authority: game_state.authority,
+        });
+        Ok(())
+    }
+    
+    /// Puts every admin action (settings, wheel, token tables, withdrawals and authority transfers)
+    /// behind `threshold` of `admins` approving a proposal.
+    /// Once enabled it stays on; the admin set can only change through an approved proposal.
+    pub fn create_multisig(ctx: Context<CreateMultisig>, admins: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        validate_admins(&admins, threshold)?;
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.game_state = ctx.accounts.game_state.key();
+        multisig.admins = admins;
+        multisig.threshold = threshold;
+        multisig.generation = 0;
+        multisig.proposal_count = 0;
+        multisig.bump = ctx.bumps.multisig;
+        ctx.accounts.game_state.multisig_enabled = true;
+        
+        msg!("Multisig enabled: {} of {} admins", threshold, multisig.admins.len());
+        Ok(())
+    }
+    
+    /// Opens proposal `multisig.proposal_count` for `action`, counting the proposer's approval
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: AdminAction) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        let index = multisig.admin_index(&ctx.accounts.proposer.key())?;
+        if let AdminAction::SetAdmins { admins, threshold } = &action {
+            validate_admins(admins, *threshold)?;
+        }
+        
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.multisig = multisig.key();
+        proposal.id = multisig.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action;
+        proposal.approvals = 1 << index;
+        proposal.generation = multisig.generation;
+        proposal.bump = ctx.bumps.proposal;
+        multisig.proposal_count = multisig
+            .proposal_count
+            .checked_add(1)
+            .ok_or(SpinWheelError::ArithmeticOverflow)?;
+        
+        msg!("Proposal {} created", proposal.id);
+        Ok(())
+    }
+    
+    /// Records an admin's approval of a proposal
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        let multisig = &ctx.accounts.multisig;
+        let proposal = &mut ctx.accounts.proposal;
+        require!(proposal.generation == multisig.generation, SpinWheelError::StaleProposal);
+        let index = multisig.admin_index(&ctx.accounts.admin.key())?;
+        proposal.approvals |= 1 << index;
+        
+        msg!("Proposal {} approved by {}", proposal.id, ctx.accounts.admin.key());
+        Ok(())
+    }
+    
+    /// Carries out a proposal once enough admins approved it, then closes it to the proposer.
+    /// Withdrawals always go to the table authority, under the same rules as the direct instructions;
+    /// queued ones are paid out by `execute_withdrawal` and `execute_token_withdrawal`.
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let approvals = check_approved(&ctx.accounts.multisig, proposal, &ctx.accounts.executor.key())?;
+        let proposal_id = proposal.id;
+        
+        match proposal.action.clone() {
+            AdminAction::UpdateSettings {
+                house_edge,
+                min_bet,
+                max_bet,
+                is_paused,
+            } => apply_settings(&mut ctx.accounts.game_state, house_edge, min_bet, max_bet, is_paused)?,
+            AdminAction::WithdrawHouseFunds { amount } => {
+                record_unqueued_withdrawal(&mut ctx.accounts.game_state, amount)?;
+                withdraw_lamports(
+                    &ctx.accounts.game_state,
+                    &ctx.accounts.house_vault.to_account_info(),
+                    &ctx.accounts.authority.to_account_info(),
+                    amount,
+                )?
+            }
+            AdminAction::QueueWithdrawal { amount } => queue_house_withdrawal(&mut ctx.accounts.game_state, amount)?,
+            AdminAction::ConfigureWithdrawalTimelock {
+                threshold,
+                delay_seconds,
+            } => apply_withdrawal_timelock(&mut ctx.accounts.game_state, threshold, delay_seconds)?,
+            AdminAction::QueueTokenWithdrawal { mint, amount } => {
+                let game_state = &mut ctx.accounts.game_state;
+                let Some(token_table) = ctx.accounts.token_table.as_mut() else {
+                    return err!(SpinWheelError::TokenAccountsMissing);
+                };
+                require_keys_eq!(token_table.game_state, game_state.key(), SpinWheelError::InvalidTokenAccount);
+                require_keys_eq!(token_table.mint, mint, SpinWheelError::InvalidTokenAccount);
+                queue_token_vault_withdrawal(game_state, token_table, amount)?
+            }
+            AdminAction::ConfigureWheel { segments } => {
+                let Some(wheel_config) = ctx.accounts.wheel_config.as_mut() else {
+                    return err!(SpinWheelError::WheelConfigMissing);
+                };
+                apply_wheel(&ctx.accounts.game_state, wheel_config, segments)?
+            }
+            AdminAction::InitializeTokenTable { .. } => return err!(SpinWheelError::WrongProposalAction),
+            AdminAction::UpdateTokenTable { mint, min_bet, max_bet } => {
+                let game_state = ctx.accounts.game_state.key();
+                let Some(token_table) = ctx.accounts.token_table.as_mut() else {
+                    return err!(SpinWheelError::TokenAccountsMissing);
+                };
+                require_keys_eq!(token_table.game_state, game_state, SpinWheelError::InvalidTokenAccount);
+                require_keys_eq!(token_table.mint, mint, SpinWheelError::InvalidTokenAccount);
+                apply_token_table_limits(token_table, min_bet, max_bet)?
+            }
+            AdminAction::ProposeAuthority { new_authority } => {
+                ctx.accounts.game_state.pending_authority = new_authority;
+                msg!("Authority transfer proposed to {}", new_authority);
+            }
+            AdminAction::SetAdmins { admins, threshold } => {
+                // Validated at creation. Bumping the generation voids every other open proposal,
+                // since their approvals index the old admin list.
+                let multisig = &mut ctx.accounts.multisig;
+                multisig.admins = admins;
+                multisig.threshold = threshold;
+                multisig.generation = multisig
+                    .generation
+                    .checked_add(1)
+                    .ok_or(SpinWheelError::ArithmeticOverflow)?;
+            }
+        }
+        
+        emit!(ProposalExecuted {
+            table_id: ctx.accounts.game_state.table_id,
+            proposal_id,
+            approvals,
+        });
+        
+        // Anchor will close `proposal` to `proposer` (close = proposer), returning rent.
+        Ok(())
+    }
+    
+    /// Carries out an approved `InitializeTokenTable` proposal, which needs its own accounts
+    /// to create the token table and vault. The executing admin pays their rent.
+    pub fn execute_initialize_token_table(ctx: Context<ExecuteInitializeTokenTable>) -> Result<()> {
+        let proposal = &ctx.accounts.proposal;
+        let approvals = check_approved(&ctx.accounts.multisig, proposal, &ctx.accounts.executor.key())?;
+        let AdminAction::InitializeTokenTable { mint, min_bet, max_bet } = proposal.action else {
+            return err!(SpinWheelError::WrongProposalAction);
+        };
+        require_keys_eq!(ctx.accounts.mint.key(), mint, SpinWheelError::InvalidTokenAccount);
+        let proposal_id = proposal.id;
+        
+        apply_new_token_table(
+            &mut ctx.accounts.token_table,
+            ctx.accounts.game_state.key(),
+            &ctx.accounts.mint,
+            ctx.accounts.token_vault.key(),
+            ctx.bumps.token_table,
+            min_bet,
+            max_bet,
+        )?;
+        
+        emit!(ProposalExecuted {
+            table_id: ctx.accounts.game_state.table_id,
+            proposal_id,
+            approvals,
+        });
+        
+        // Anchor will close `proposal` to `proposer` (close = proposer), returning rent.
+        Ok(())
+    }
+    
+    /// Withdraws a proposal that is no longer wanted, returning its rent to the proposer
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        msg!("Proposal {} cancelled", ctx.accounts.proposal.id);
+        Ok(())
+    }
+    
+    /// Sets the player's own limits. Tightening takes effect immediately;
+    /// loosening any limit only after LIMIT_LOOSENING_DELAY.
+    pub fn set_limits(ctx: Context<SetLimits>, settings: LimitSettings) -> Result<()> {
+        let player_limits = &mut ctx.accounts.player_limits;
+        player_limits.init_if_new(ctx.accounts.player.key(), ctx.bumps.player_limits);
+        player_limits
+            .limits
+            .request(Clock::get()?.unix_timestamp, settings)
+            .map_err(SpinWheelError::from)?;
+        
+        msg!("Limits updated; loosening pending until {}", player_limits.limits.pending_at);
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
+#[instruction(table_id: u64)]
 pub struct Initialize<'info> {
     #[account(
         init,
         payer = authority,
         space = 8 + GameState::LEN, // Explicitly include discriminator
-        seeds = [b"game_state"],
+        seeds = [b"game_state", authority.key().as_ref(), table_id.to_le_bytes().as_ref()],
         bump
     )]
     pub game_state: Account<'info, GameState>,
@@ -237,12 +826,21 @@ pub struct Initialize<'info> {
         init,
         payer = authority,
         space = 8, // Minimal space for system account
-        seeds = [b"house_vault"],
+        seeds = [b"house_vault", game_state.key().as_ref()],
         bump
     )]
     /// CHECK: Program-owned PDA vault (lamports-only)
     pub house_vault: UncheckedAccount<'info>,
     
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + WheelConfig::LEN,
+        seeds = [b"wheel_config", game_state.key().as_ref()],
+        bump
+    )]
+    pub wheel_config: Account<'info, WheelConfig>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
@@ -250,70 +848,617 @@ pub struct Initialize<'info> {
 }
 
 #[derive(Accounts)]
-pub struct Spin<'info> {
+pub struct ConfigureWheel<'info> {
     #[account(
-        mut,
-        seeds = [b"game_state"],
-        bump = game_state.bump // Validate bump
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump, // Validate bump
+        constraint = game_state.authority == authority.key()
     )]
     pub game_state: Account<'info, GameState>,
     
     #[account(
         mut,
-        seeds = [b"house_vault"],
-        bump
+        seeds = [b"wheel_config", game_state.key().as_ref()],
+        bump = wheel_config.bump
     )]
-    /// CHECK: Program-owned PDA vault (lamports-only)
-    pub house_vault: UncheckedAccount<'info>,
+    pub wheel_config: Account<'info, WheelConfig>,
     
-    #[account(mut)]
-    pub player: Signer<'info>,
-    
-    /// CHECK: Sysvar for slot hashes
-    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::id())]
-    pub recent_slothashes: UncheckedAccount<'info>,
-    
-    pub system_program: Program<'info, System>,
+    pub authority: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateSettings<'info> {
+pub struct Spin<'info> {
     #[account(
         mut,
-        seeds = [b"game_state"],
-        bump = game_state.bump, // Validate bump
-        constraint = game_state.authority == authority.key()
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump // Validate bump
     )]
     pub game_state: Account<'info, GameState>,
     
-    pub authority: Signer<'info>,
-}
+    #[account(
+        mut,
+        seeds = [b"house_vault", game_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Program-owned PDA vault (lamports-only)
+    pub house_vault: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"wheel_config", game_state.key().as_ref()],
+        bump = wheel_config.bump
+    )]
+    pub wheel_config: Account<'info, WheelConfig>,
+    
+    #[account(
+        init,
+        payer = player,
+        space = 8 + PendingSpin::LEN,
+        seeds = [b"spin", game_state.key().as_ref(), player.key().as_ref()],
+        bump
+    )]
+    pub pending_spin: Account<'info, PendingSpin>,
+    
+    // One stats account per player and table, created on the first spin
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + PlayerStats::LEN,
+        seeds = [b"player", game_state.key().as_ref(), player.key().as_ref()],
+        bump
+    )]
+    pub player_stats: Account<'info, PlayerStats>,
+    
+    // The player's own limits, created empty (no limits) on their first game
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + PlayerLimits::LEN,
+        seeds = [b"limits", player.key().as_ref()],
+        bump
+    )]
+    pub player_limits: Account<'info, PlayerLimits>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    /// House must co-sign so the commitment provably originates from it.
+    #[account(constraint = game_state.authority == house.key())]
+    pub house: Signer<'info>,
+    
+    pub token: TokenAccounts<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FulfilSpin<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump // Validate bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", game_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Program-owned PDA vault (lamports-only)
+    pub house_vault: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"wheel_config", game_state.key().as_ref()],
+        bump = wheel_config.bump
+    )]
+    pub wheel_config: Account<'info, WheelConfig>,
+    
+    // Close the pending spin (rent) to player once settled
+    #[account(
+        mut,
+        seeds = [b"spin", game_state.key().as_ref(), player.key().as_ref()],
+        bump = pending_spin.bump,
+        close = player
+    )]
+    pub pending_spin: Account<'info, PendingSpin>,
+    
+    #[account(
+        mut,
+        seeds = [b"player", game_state.key().as_ref(), player.key().as_ref()],
+        bump = player_stats.bump
+    )]
+    pub player_stats: Account<'info, PlayerStats>,
+    
+    /// CHECK: only receives the payout and rent; the pending spin PDA is derived from it
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+    
+    /// House must sign to reveal its seed (must match game_state.authority).
+    #[account(constraint = game_state.authority == house.key())]
+    pub house: Signer<'info>,
+    
+    /// CHECK: Sysvar for slot hashes
+    #[account(address = anchor_lang::solana_program::sysvar::slot_hashes::id())]
+    pub recent_slothashes: UncheckedAccount<'info>,
+    
+    // The player's limits, to credit what is paid back; placing the game created them
+    #[account(mut, seeds = [b"limits", player.key().as_ref()], bump = player_limits.bump)]
+    pub player_limits: Account<'info, PlayerLimits>,
+    
+    pub token: TokenAccounts<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundSpin<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump // Validate bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", game_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Program-owned PDA vault (lamports-only)
+    pub house_vault: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"spin", game_state.key().as_ref(), player.key().as_ref()],
+        bump = pending_spin.bump,
+        close = player
+    )]
+    pub pending_spin: Account<'info, PendingSpin>,
+    
+    /// CHECK: only receives the refund and rent; the pending spin PDA is derived from it.
+    /// No signature needed, so anyone can clear an expired spin.
+    #[account(mut)]
+    pub player: UncheckedAccount<'info>,
+    
+    // The player's limits, to credit what is paid back; placing the game created them
+    #[account(mut, seeds = [b"limits", player.key().as_ref()], bump = player_limits.bump)]
+    pub player_limits: Account<'info, PlayerLimits>,
+    
+    pub token: TokenAccounts<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetLimits<'info> {
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = 8 + PlayerLimits::LEN,
+        seeds = [b"limits", player.key().as_ref()],
+        bump
+    )]
+    pub player_limits: Account<'info, PlayerLimits>,
+    
+    #[account(mut)]
+    pub player: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for a spin staked in an SPL mint; all omitted for SOL spins.
+/// They are checked against each other in `leg`, since any of them may be missing.
+#[derive(Accounts)]
+pub struct TokenAccounts<'info> {
+    #[account(mut)]
+    pub token_table: Option<Account<'info, TokenTable>>,
+    
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub player_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+    
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTokenTable<'info> {
+    #[account(
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump, // Validate bump
+        constraint = game_state.authority == authority.key()
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + TokenTable::LEN,
+        seeds = [b"token_table", game_state.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub token_table: Account<'info, TokenTable>,
+    
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"token_vault", token_table.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_table,
+        token::token_program = token_program
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTokenTable<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump, // Validate bump
+        constraint = game_state.authority == authority.key()
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_table", game_state.key().as_ref(), token_table.mint.as_ref()],
+        bump = token_table.bump
+    )]
+    pub token_table: Account<'info, TokenTable>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundTokenVault<'info> {
+    #[account(
+        seeds = [b"token_table", token_table.game_state.as_ref(), mint.key().as_ref()],
+        bump = token_table.bump,
+        has_one = mint
+    )]
+    pub token_table: Account<'info, TokenTable>,
+    
+    #[account(mut, address = token_table.vault)]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub funder: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteTokenWithdrawal<'info> {
+    #[account(
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump, // Validate bump
+        constraint = game_state.authority == authority.key()
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"token_table", game_state.key().as_ref(), mint.key().as_ref()],
+        bump = token_table.bump,
+        has_one = mint
+    )]
+    pub token_table: Account<'info, TokenTable>,
+    
+    #[account(mut, address = token_table.vault)]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub authority_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSettings<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump, // Validate bump
+        constraint = game_state.authority == authority.key()
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawHouseFunds<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump // Validate bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", game_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Program-owned PDA vault (lamports-only)
+    pub house_vault: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = game_state.authority == authority.key())]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump // Validate bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", game_state.key().as_ref()],
+        bump
+    )]
+    /// CHECK: Program-owned PDA vault (lamports-only)
+    pub house_vault: UncheckedAccount<'info>,
+    
+    #[account(mut, constraint = game_state.authority == authority.key())]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump, // Validate bump
+        constraint = game_state.authority == authority.key()
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump, // Validate bump
+        constraint = game_state.pending_authority == new_authority.key() @ SpinWheelError::NotPendingAuthority
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMultisig<'info> {
+    #[account(
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump, // Validate bump
+        constraint = game_state.authority == authority.key()
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Multisig::LEN,
+        seeds = [b"multisig", game_state.key().as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.game_state.as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::LEN,
+        seeds = [b"proposal", multisig.key().as_ref(), multisig.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    #[account(
+        seeds = [b"multisig", multisig.game_state.as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.key().as_ref(), proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        has_one = multisig
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    pub admin: Signer<'info>,
+}
 
 #[derive(Accounts)]
-pub struct WithdrawHouseFunds<'info> {
+pub struct ExecuteProposal<'info> {
     #[account(
-        seeds = [b"game_state"],
+        mut,
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
         bump = game_state.bump // Validate bump
     )]
     pub game_state: Account<'info, GameState>,
     
     #[account(
         mut,
-        seeds = [b"house_vault"],
+        seeds = [b"multisig", game_state.key().as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.key().as_ref(), proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        has_one = multisig,
+        has_one = proposer,
+        close = proposer
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    /// CHECK: Receives the proposal rent; checked by has_one
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    
+    pub executor: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"house_vault", game_state.key().as_ref()],
         bump
     )]
     /// CHECK: Program-owned PDA vault (lamports-only)
     pub house_vault: UncheckedAccount<'info>,
     
-    #[account(mut, constraint = game_state.authority == authority.key())]
-    pub authority: Signer<'info>,
+    /// CHECK: Receives SOL withdrawals; must be the table authority
+    #[account(mut, address = game_state.authority)]
+    pub authority: UncheckedAccount<'info>,
+    
+    // Wheel changes only
+    #[account(
+        mut,
+        seeds = [b"wheel_config", game_state.key().as_ref()],
+        bump = wheel_config.bump
+    )]
+    pub wheel_config: Option<Account<'info, WheelConfig>>,
+    
+    // Token withdrawals and limit changes only; checked against the proposal in `execute_proposal`
+    #[account(mut)]
+    pub token_table: Option<Account<'info, TokenTable>>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteInitializeTokenTable<'info> {
+    #[account(
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump // Validate bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
+    #[account(
+        seeds = [b"multisig", game_state.key().as_ref()],
+        bump = multisig.bump
+    )]
+    pub multisig: Account<'info, Multisig>,
+    
+    #[account(
+        mut,
+        seeds = [b"proposal", multisig.key().as_ref(), proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        has_one = multisig,
+        has_one = proposer,
+        close = proposer
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    /// CHECK: Receives the proposal rent; checked by has_one
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    
+    #[account(
+        init,
+        payer = executor,
+        space = 8 + TokenTable::LEN,
+        seeds = [b"token_table", game_state.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub token_table: Account<'info, TokenTable>,
+    
+    #[account(
+        init,
+        payer = executor,
+        seeds = [b"token_vault", token_table.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = token_table,
+        token::token_program = token_program
+    )]
+    pub token_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    pub mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    #[account(
+        mut,
+        has_one = proposer,
+        close = proposer
+    )]
+    pub proposal: Account<'info, Proposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct FundHouseVault<'info> {
+    #[account(
+        seeds = [b"game_state", game_state.creator.as_ref(), game_state.table_id.to_le_bytes().as_ref()],
+        bump = game_state.bump // Validate bump
+    )]
+    pub game_state: Account<'info, GameState>,
+    
     #[account(
         mut,
-        seeds = [b"house_vault"],
+        seeds = [b"house_vault", game_state.key().as_ref()],
         bump
     )]
     /// CHECK: Program-owned PDA vault (lamports-only)
@@ -336,6 +1481,21 @@ pub struct GameState {
     pub min_bet: u64,
     pub max_bet: u64,
     pub bump: u8,                // Store PDA bump
+    pub reserved: u64,           // winning payouts owed to pending spins
+    pub table_id: u64,           // PDA seed with creator; tables are independent
+    pub pending_spins: u64,      // spins awaiting fulfilment, in any currency
+    pub pending_authority: Pubkey, // set by propose_authority; default when none
+    pub multisig_enabled: bool,  // admin actions need an approved proposal
+    pub withdrawal_threshold: u64, // larger house vault withdrawals must be queued
+    pub withdrawal_delay: i64,   // seconds a queued withdrawal waits
+    pub queued_withdrawal: u64,  // lamports; 0 when nothing is queued
+    pub withdrawal_unlocks_at: i64, // unix timestamp the queued withdrawal becomes executable
+    pub creator: Pubkey,         // PDA seed with table_id; stays put when the authority changes
+    pub pending_withdrawal_threshold: u64, // loosened timelock waiting for timelock_change_at
+    pub pending_withdrawal_delay: i64,
+    pub timelock_change_at: i64, // unix timestamp the pending timelock applies; 0 when none
+    pub withdrawal_window: i64,  // index of the window window_withdrawn counts
+    pub window_withdrawn: u64,   // lamports withdrawn without queueing in that window
 }
 
 impl GameState {
@@ -347,12 +1507,312 @@ impl GameState {
         1 +   // is_paused
         8 +   // min_bet
         8 +   // max_bet
+        1 +   // bump
+        8 +   // reserved
+        8 +   // table_id
+        8 +   // pending_spins
+        32 +  // pending_authority
+        1 +   // multisig_enabled
+        8 +   // withdrawal_threshold
+        8 +   // withdrawal_delay
+        8 +   // queued_withdrawal
+        8 +   // withdrawal_unlocks_at
+        32 +  // creator
+        8 +   // pending_withdrawal_threshold
+        8 +   // pending_withdrawal_delay
+        8 +   // timelock_change_at
+        8 +   // withdrawal_window
+        8;    // window_withdrawn
+    
+    /// Applies a loosened timelock whose delay has passed and starts a new window when it turns
+    fn refresh_timelock(&mut self, now: i64) {
+        if self.timelock_change_at != 0 && now >= self.timelock_change_at {
+            self.withdrawal_threshold = self.pending_withdrawal_threshold;
+            self.withdrawal_delay = self.pending_withdrawal_delay;
+            self.timelock_change_at = 0;
+        }
+        if now / WITHDRAWAL_WINDOW_SECS != self.withdrawal_window {
+            self.withdrawal_window = now / WITHDRAWAL_WINDOW_SECS;
+            self.window_withdrawn = 0;
+        }
+    }
+}
+
+/// M-of-N admin set for one table
+#[account]
+pub struct Multisig {
+    pub game_state: Pubkey,
+    pub admins: Vec<Pubkey>,
+    pub threshold: u8,           // approvals needed to execute a proposal
+    pub generation: u32,         // bumped when admins change, voiding open proposals
+    pub proposal_count: u64,     // id of the next proposal
+    pub bump: u8,
+}
+
+impl Multisig {
+    pub const LEN: usize = 32 +  // game_state
+        4 + MAX_ADMINS * 32 + // admins
+        1 +   // threshold
+        4 +   // generation
+        8 +   // proposal_count
+        1;    // bump
+    
+    fn admin_index(&self, key: &Pubkey) -> Result<usize> {
+        self.admins
+            .iter()
+            .position(|admin| admin == key)
+            .ok_or_else(|| error!(SpinWheelError::NotAdmin))
+    }
+}
+
+/// An admin action collecting approvals
+#[account]
+pub struct Proposal {
+    pub multisig: Pubkey,
+    pub id: u64,
+    pub proposer: Pubkey,        // receives the rent back
+    pub action: AdminAction,
+    pub approvals: u16,          // bitmap over multisig.admins
+    pub generation: u32,         // multisig generation the approvals belong to
+    pub bump: u8,
+}
+
+impl Proposal {
+    pub const LEN: usize = 32 +  // multisig
+        8 +   // id
+        32 +  // proposer
+        AdminAction::LEN + // action
+        2 +   // approvals
+        4 +   // generation
+        1;    // bump
+}
+
+/// What a proposal does once approved
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum AdminAction {
+    UpdateSettings {
+        house_edge: Option<u8>,
+        min_bet: Option<u64>,
+        max_bet: Option<u64>,
+        is_paused: Option<bool>,
+    },
+    WithdrawHouseFunds {
+        amount: u64,
+    },
+    QueueWithdrawal {
+        amount: u64,
+    },
+    ConfigureWithdrawalTimelock {
+        threshold: u64,
+        delay_seconds: i64,
+    },
+    QueueTokenWithdrawal {
+        mint: Pubkey,
+        amount: u64,
+    },
+    SetAdmins {
+        admins: Vec<Pubkey>,
+        threshold: u8,
+    },
+    ConfigureWheel {
+        segments: Vec<Segment>,
+    },
+    InitializeTokenTable {
+        mint: Pubkey,
+        min_bet: u64,
+        max_bet: u64,
+    },
+    UpdateTokenTable {
+        mint: Pubkey,
+        min_bet: Option<u64>,
+        max_bet: Option<u64>,
+    },
+    ProposeAuthority {
+        new_authority: Pubkey,
+    },
+}
+
+impl AdminAction {
+    // Largest variant: SetAdmins with a full admin list
+    pub const LEN: usize = 1 + 4 + MAX_ADMINS * 32 + 1;
+}
+
+/// A table's vault and limits for one whitelisted SPL mint; amounts are in the mint's base units
+#[account]
+pub struct TokenTable {
+    pub game_state: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,           // token account owned by this PDA
+    pub min_bet: u64,
+    pub max_bet: u64,
+    pub reserved: u64,           // winning payouts owed to pending spins
+    pub total_wagered: u64,
+    pub total_paid_out: u64,
+    pub bump: u8,
+    pub queued_withdrawal: u64,  // 0 when nothing is queued
+    pub withdrawal_unlocks_at: i64, // unix timestamp the queued withdrawal becomes executable
+}
+
+impl TokenTable {
+    pub const LEN: usize = 32 +  // game_state
+        32 +  // mint
+        32 +  // vault
+        8 +   // min_bet
+        8 +   // max_bet
+        8 +   // reserved
+        8 +   // total_wagered
+        8 +   // total_paid_out
+        1 +   // bump
+        8 +   // queued_withdrawal
+        8;    // withdrawal_unlocks_at
+}
+
+#[account]
+pub struct WheelConfig {
+    pub segments: Vec<Segment>, // index is the prediction a player bets on
+    pub total_weight: u32,      // sum of segment weights
+    pub max_multiplier: u16,    // best-paying segment, used for the solvency check
+    pub bump: u8,
+}
+
+impl WheelConfig {
+    pub const LEN: usize = 4 + MAX_SEGMENTS * Segment::LEN + // segments
+        4 +   // total_weight
+        2 +   // max_multiplier
+        1;    // bump
+}
+
+/// One slice of the wheel
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    pub weight: u16,     // relative odds of landing here
+    pub multiplier: u16, // total return on a win, stake included, before house edge
+}
+
+impl Segment {
+    pub const LEN: usize = 2 + 2;
+}
+
+#[account]
+pub struct PendingSpin {
+    pub player: Pubkey,
+    pub bet_amount: u64,
+    pub prediction: u8,
+    pub payout_if_won: u64,         // reserved in game_state until fulfilment or refund
+    pub house_commitment: [u8; 32], // hash(house_seed), fixed at spin
+    pub player_seed: [u8; 32],
+    pub spin_slot: u64,
+    pub bump: u8,
+    pub mint: Pubkey,               // bet currency; default is SOL
+}
+
+impl PendingSpin {
+    pub const LEN: usize = 32 +  // player
+        8 +   // bet_amount
+        1 +   // prediction
+        8 +   // payout_if_won
+        32 +  // house_commitment
+        32 +  // player_seed
+        8 +   // spin_slot
+        1 +   // bump
+        32;   // mint
+}
+
+/// Player-owned responsible gaming limits. The rules are shared by every program in
+/// `casino_common::limits`; this account only ties them to the player.
+#[account]
+pub struct PlayerLimits {
+    pub player: Pubkey,
+    pub limits: LimitState,
+    pub bump: u8,
+}
+
+impl PlayerLimits {
+    pub const LEN: usize = 32 + LimitState::LEN + 1;
+    
+    /// Fills in the owner of a freshly created account
+    fn init_if_new(&mut self, player: Pubkey, bump: u8) {
+        if self.player == Pubkey::default() {
+            self.player = player;
+            self.bump = bump;
+        }
+    }
+}
+
+impl From<LimitViolation> for SpinWheelError {
+    fn from(violation: LimitViolation) -> Self {
+        match violation {
+            LimitViolation::SelfExcluded => SpinWheelError::SelfExcluded,
+            LimitViolation::CooldownActive => SpinWheelError::CooldownActive,
+            LimitViolation::AboveBetLimit => SpinWheelError::AboveBetLimit,
+            LimitViolation::LossLimitReached => SpinWheelError::LossLimitReached,
+            LimitViolation::CurrencyNotLimited => SpinWheelError::CurrencyNotLimited,
+            LimitViolation::InvalidSettings => SpinWheelError::InvalidLimits,
+            LimitViolation::Overflow => SpinWheelError::ArithmeticOverflow,
+        }
+    }
+}
+
+/// One player's history at one table. Amounts are only comparable within one currency,
+/// so everything is tallied per mint; only the first MAX_STATS_CURRENCIES played are kept.
+#[account]
+pub struct PlayerStats {
+    pub player: Pubkey,
+    pub game_state: Pubkey,
+    pub currencies: Vec<CurrencyStats>,
+    pub last_spin_timestamp: i64,
+    pub bump: u8,
+}
+
+impl PlayerStats {
+    pub const LEN: usize = 32 +  // player
+        32 +  // game_state
+        4 + MAX_STATS_CURRENCIES * CurrencyStats::LEN + // currencies
+        8 +   // last_spin_timestamp
         1;    // bump
+    
+    /// The tallies for `mint`, started on its first fulfilled spin while there is room
+    fn stats_in(&mut self, mint: Pubkey) -> Option<&mut CurrencyStats> {
+        let index = match self.currencies.iter().position(|stats| stats.mint == mint) {
+            Some(index) => index,
+            None if self.currencies.len() < MAX_STATS_CURRENCIES => {
+                self.currencies.push(CurrencyStats {
+                    mint,
+                    ..CurrencyStats::default()
+                });
+                self.currencies.len() - 1
+            }
+            None => return None,
+        };
+        self.currencies.get_mut(index)
+    }
+}
+
+/// A player's fulfilled spins in one currency, in its base units; the default mint is SOL
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct CurrencyStats {
+    pub mint: Pubkey,
+    pub games_played: u64,
+    pub total_wagered: u64,
+    pub total_won: u64,
+    pub biggest_win: u64,
+    pub current_streak: i32, // consecutive wins (> 0) or losses (< 0)
+}
+
+impl CurrencyStats {
+    pub const LEN: usize = 32 +  // mint
+        8 +   // games_played
+        8 +   // total_wagered
+        8 +   // total_won
+        8 +   // biggest_win
+        4;    // current_streak
 }
 
 #[event]
 pub struct SpinResult {
+    pub table_id: u64,
     pub player: Pubkey,
+    pub mint: Pubkey, // default for SOL
     pub bet_amount: u64,
     pub prediction: u8,
     pub result: u8,
@@ -360,6 +1820,46 @@ pub struct SpinResult {
     pub is_winner: bool,
     pub timestamp: i64,
     pub house_edge: u8, // Added for transparency
+    pub multiplier: u16,
+    pub house_seed: [u8; 32], // with player_seed and spin_slot, lets anyone recompute result
+    pub player_seed: [u8; 32],
+    pub spin_slot: u64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub table_id: u64,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalQueued {
+    pub table_id: u64,
+    pub mint: Pubkey, // default for the SOL house vault
+    pub amount: u64,
+    pub unlocks_at: i64,
+}
+
+#[event]
+pub struct WithdrawalExecuted {
+    pub table_id: u64,
+    pub mint: Pubkey, // default for the SOL house vault
+    pub amount: u64,
+}
+
+#[event]
+pub struct WithdrawalCancelled {
+    pub table_id: u64,
+    pub mint: Pubkey, // default for the SOL house vault
+    pub amount: u64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub table_id: u64,
+    pub proposal_id: u64,
+    pub approvals: u8,
 }
 
 #[error_code]
@@ -372,7 +1872,7 @@ pub enum SpinWheelError {
     BetTooLow,
     #[msg("Bet amount is too high")]
     BetTooHigh,
-    #[msg("Invalid prediction. Must be between 0-7")]
+    #[msg("Invalid prediction. Must be a segment on the wheel")]
     InvalidPrediction,
     #[msg("Insufficient house funds for payout")]
     InsufficientHouseFunds,
@@ -384,4 +1884,471 @@ pub enum SpinWheelError {
     GameNotPaused,
     #[msg("Arithmetic overflow occurred")]
     ArithmeticOverflow,
-}
\ No newline at end of file
+    #[msg("House seed does not match commitment")]
+    InvalidHouseSeed,
+    #[msg("Slot hash for the spin slot is not available")]
+    SlotHashUnavailable,
+    #[msg("Spin can still be fulfilled by the house")]
+    FulfilWindowOpen,
+    #[msg("Invalid wheel. Needs 2-54 segments with non-zero weights and a paying segment")]
+    InvalidWheel,
+    #[msg("Wheel cannot change while spins are pending")]
+    SpinsPending,
+    #[msg("Token accounts are required for spins in an SPL mint")]
+    TokenAccountsMissing,
+    #[msg("Token account does not match the token table")]
+    InvalidTokenAccount,
+    #[msg("Signer is not the proposed authority")]
+    NotPendingAuthority,
+    #[msg("Multisig is enabled; use a proposal")]
+    MultisigRequired,
+    #[msg("Invalid multisig. Needs 1-10 distinct admins and a threshold between 1 and the admin count")]
+    InvalidMultisig,
+    #[msg("Signer is not a multisig admin")]
+    NotAdmin,
+    #[msg("Proposal predates the current admin set")]
+    StaleProposal,
+    #[msg("Proposal does not have enough approvals")]
+    NotEnoughApprovals,
+    #[msg("Withdrawal is above the threshold and must be queued")]
+    WithdrawalMustBeQueued,
+    #[msg("A withdrawal is already queued")]
+    WithdrawalAlreadyQueued,
+    #[msg("No withdrawal is queued")]
+    NoQueuedWithdrawal,
+    #[msg("Queued withdrawal is still timelocked")]
+    WithdrawalLocked,
+    #[msg("Withdrawal delay cannot be negative")]
+    InvalidWithdrawalDelay,
+    #[msg("Player is self-excluded")]
+    SelfExcluded,
+    #[msg("Player's session cooldown has not passed")]
+    CooldownActive,
+    #[msg("Bet is above the player's max bet")]
+    AboveBetLimit,
+    #[msg("Bet would exceed the player's loss limit")]
+    LossLimitReached,
+    #[msg("Mints with a transfer fee are not supported")]
+    TransferFeeMint,
+    #[msg("Wheel config account is required for this proposal")]
+    WheelConfigMissing,
+    #[msg("Proposal action cannot be executed by this instruction")]
+    WrongProposalAction,
+    #[msg("Player's limits do not cover this currency")]
+    CurrencyNotLimited,
+    #[msg("Invalid limits. Up to 4 currencies, each listed once")]
+    InvalidLimits,
+}
+
+/// Validated token accounts for one spin, borrowed out of `TokenAccounts`
+struct TokenLeg<'a, 'info> {
+    table: &'a mut Account<'info, TokenTable>,
+    vault: &'a InterfaceAccount<'info, TokenAccount>,
+    player_account: &'a InterfaceAccount<'info, TokenAccount>,
+    mint: &'a InterfaceAccount<'info, Mint>,
+    token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'info> TokenAccounts<'info> {
+    /// Mint of the passed token accounts, or the default pubkey (SOL) when none are passed
+    fn mint_key(&self) -> Pubkey {
+        self.mint.as_ref().map_or(Pubkey::default(), |mint| mint.key())
+    }
+    
+    /// Checks that every account is present and belongs to the table's `mint` vault and `player`
+    fn leg(&mut self, game_state: Pubkey, mint: Pubkey, player: Pubkey) -> Result<TokenLeg<'_, 'info>> {
+        let (Some(table), Some(vault), Some(player_account), Some(mint_account), Some(token_program)) = (
+            self.token_table.as_mut(),
+            self.vault_token_account.as_ref(),
+            self.player_token_account.as_ref(),
+            self.mint.as_ref(),
+            self.token_program.as_ref(),
+        ) else {
+            return err!(SpinWheelError::TokenAccountsMissing);
+        };
+        require_keys_eq!(mint_account.key(), mint, SpinWheelError::InvalidTokenAccount);
+        require_keys_eq!(table.game_state, game_state, SpinWheelError::InvalidTokenAccount);
+        require_keys_eq!(table.mint, mint, SpinWheelError::InvalidTokenAccount);
+        require_keys_eq!(table.vault, vault.key(), SpinWheelError::InvalidTokenAccount);
+        require_keys_eq!(player_account.mint, mint, SpinWheelError::InvalidTokenAccount);
+        require_keys_eq!(player_account.owner, player, SpinWheelError::InvalidTokenAccount);
+        Ok(TokenLeg {
+            table,
+            vault,
+            player_account,
+            mint: mint_account,
+            token_program,
+        })
+    }
+}
+
+impl<'info> TokenLeg<'_, 'info> {
+    /// Moves `amount` from the player's token account into the vault
+    fn deposit(&self, player: &Signer<'info>, amount: u64) -> Result<()> {
+        token_interface::transfer_checked(
+            CpiContext::new(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.player_account.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: player.to_account_info(),
+                },
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+    
+    /// Pays `amount` from the vault to the player's token account, signed by the token table
+    fn pay(&self, amount: u64) -> Result<()> {
+        let game_state = self.table.game_state;
+        let mint = self.table.mint;
+        let seeds: &[&[u8]] = &[b"token_table", game_state.as_ref(), mint.as_ref(), &[self.table.bump]];
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                TransferChecked {
+                    from: self.vault.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.player_account.to_account_info(),
+                    authority: self.table.to_account_info(),
+                },
+                &[seeds],
+            ),
+            amount,
+            self.mint.decimals,
+        )
+    }
+}
+
+/// Applies a settings change, leaving fields passed as None untouched
+fn apply_settings(
+    game_state: &mut GameState,
+    house_edge: Option<u8>,
+    min_bet: Option<u64>,
+    max_bet: Option<u64>,
+    is_paused: Option<bool>,
+) -> Result<()> {
+    if let Some(edge) = house_edge {
+        require!(edge <= 20, SpinWheelError::InvalidHouseEdge);
+        game_state.house_edge = edge;
+    }
+    
+    // Apply min/max updates atomically to avoid invalid states
+    if min_bet.is_some() || max_bet.is_some() {
+        let new_min = min_bet.unwrap_or(game_state.min_bet);
+        let new_max = max_bet.unwrap_or(game_state.max_bet);
+        require!(new_min > 0, SpinWheelError::InvalidBetRange);
+        require!(new_max >= new_min, SpinWheelError::InvalidBetRange);
+        game_state.min_bet = new_min;
+        game_state.max_bet = new_max;
+    }
+    
+    if let Some(paused) = is_paused {
+        game_state.is_paused = paused;
+    }
+    
+    msg!("Settings updated: house_edge={:?}, min_bet={:?}, max_bet={:?}, is_paused={:?}",
+        house_edge, min_bet, max_bet, is_paused);
+    Ok(())
+}
+
+/// Validates and installs a wheel layout; only while no spin is pending
+/// (expired spins can be cleared by anyone through `refund_spin`)
+fn apply_wheel(game_state: &GameState, wheel_config: &mut WheelConfig, segments: Vec<Segment>) -> Result<()> {
+    require!(game_state.pending_spins == 0, SpinWheelError::SpinsPending);
+    require!(
+        (MIN_SEGMENTS..=MAX_SEGMENTS).contains(&segments.len()),
+        SpinWheelError::InvalidWheel
+    );
+    require!(segments.iter().all(|segment| segment.weight > 0), SpinWheelError::InvalidWheel);
+    
+    let total_weight: u32 = segments.iter().map(|segment| segment.weight as u32).sum();
+    let max_multiplier = segments
+        .iter()
+        .map(|segment| segment.multiplier)
+        .max()
+        .unwrap_or(0);
+    require!(max_multiplier > 0, SpinWheelError::InvalidWheel);
+    
+    wheel_config.segments = segments;
+    wheel_config.total_weight = total_weight;
+    wheel_config.max_multiplier = max_multiplier;
+    
+    msg!(
+        "Wheel configured: {} segments, total weight {}, max multiplier {}x",
+        wheel_config.segments.len(),
+        total_weight,
+        max_multiplier
+    );
+    Ok(())
+}
+
+/// Fills in a freshly created token table for `mint`
+fn apply_new_token_table(
+    token_table: &mut TokenTable,
+    game_state: Pubkey,
+    mint: &InterfaceAccount<Mint>,
+    vault: Pubkey,
+    bump: u8,
+    min_bet: u64,
+    max_bet: u64,
+) -> Result<()> {
+    require!(min_bet > 0 && max_bet >= min_bet, SpinWheelError::InvalidBetRange);
+    require!(!has_transfer_fee(&mint.to_account_info())?, SpinWheelError::TransferFeeMint);
+    
+    token_table.game_state = game_state;
+    token_table.mint = mint.key();
+    token_table.vault = vault;
+    token_table.min_bet = min_bet;
+    token_table.max_bet = max_bet;
+    token_table.reserved = 0;
+    token_table.total_wagered = 0;
+    token_table.total_paid_out = 0;
+    token_table.bump = bump;
+    token_table.queued_withdrawal = 0;
+    token_table.withdrawal_unlocks_at = 0;
+    
+    msg!("Token table initialized for mint {}", token_table.mint);
+    Ok(())
+}
+
+/// Updates a token table's bet limits, leaving fields passed as None untouched
+fn apply_token_table_limits(token_table: &mut TokenTable, min_bet: Option<u64>, max_bet: Option<u64>) -> Result<()> {
+    // Apply min/max updates atomically to avoid invalid states
+    if min_bet.is_some() || max_bet.is_some() {
+        let new_min = min_bet.unwrap_or(token_table.min_bet);
+        let new_max = max_bet.unwrap_or(token_table.max_bet);
+        require!(new_min > 0, SpinWheelError::InvalidBetRange);
+        require!(new_max >= new_min, SpinWheelError::InvalidBetRange);
+        token_table.min_bet = new_min;
+        token_table.max_bet = new_max;
+    }
+    
+    msg!("Token table updated: min_bet={:?}, max_bet={:?}", min_bet, max_bet);
+    Ok(())
+}
+
+/// Sets the timelock threshold and delay for house vault withdrawals.
+/// Tightened fields apply at once; if anything is loosened, the whole request waits the
+/// (tightened) withdrawal delay. A new request replaces one still pending.
+fn apply_withdrawal_timelock(game_state: &mut GameState, threshold: u64, delay_seconds: i64) -> Result<()> {
+    require!(delay_seconds >= 0, SpinWheelError::InvalidWithdrawalDelay);
+    let now = Clock::get()?.unix_timestamp;
+    game_state.refresh_timelock(now);
+    game_state.withdrawal_threshold = game_state.withdrawal_threshold.min(threshold);
+    game_state.withdrawal_delay = game_state.withdrawal_delay.max(delay_seconds);
+    
+    if game_state.withdrawal_threshold == threshold && game_state.withdrawal_delay == delay_seconds {
+        game_state.timelock_change_at = 0;
+    } else {
+        game_state.pending_withdrawal_threshold = threshold;
+        game_state.pending_withdrawal_delay = delay_seconds;
+        game_state.timelock_change_at = now.saturating_add(game_state.withdrawal_delay);
+    }
+    
+    msg!(
+        "Withdrawal timelock: above {} lamports, {}s delay; loosening pending until {}",
+        game_state.withdrawal_threshold,
+        game_state.withdrawal_delay,
+        game_state.timelock_change_at
+    );
+    Ok(())
+}
+
+/// Counts an unqueued house vault withdrawal against the window's threshold
+fn record_unqueued_withdrawal(game_state: &mut GameState, amount: u64) -> Result<()> {
+    game_state.refresh_timelock(Clock::get()?.unix_timestamp);
+    let withdrawn = game_state
+        .window_withdrawn
+        .checked_add(amount)
+        .ok_or(SpinWheelError::ArithmeticOverflow)?;
+    require!(withdrawn <= game_state.withdrawal_threshold, SpinWheelError::WithdrawalMustBeQueued);
+    game_state.window_withdrawn = withdrawn;
+    Ok(())
+}
+
+/// Queues `amount` to unlock after the table's withdrawal delay
+fn queue_house_withdrawal(game_state: &mut GameState, amount: u64) -> Result<()> {
+    require!(amount > 0, SpinWheelError::InvalidAmount);
+    require!(game_state.queued_withdrawal == 0, SpinWheelError::WithdrawalAlreadyQueued);
+    
+    let now = Clock::get()?.unix_timestamp;
+    game_state.refresh_timelock(now);
+    let unlocks_at = now
+        .checked_add(game_state.withdrawal_delay)
+        .ok_or(SpinWheelError::ArithmeticOverflow)?;
+    game_state.queued_withdrawal = amount;
+    game_state.withdrawal_unlocks_at = unlocks_at;
+    
+    emit!(WithdrawalQueued {
+        table_id: game_state.table_id,
+        mint: Pubkey::default(),
+        amount,
+        unlocks_at,
+    });
+    Ok(())
+}
+
+/// Queues `amount` out of a token table's vault to unlock after the table's withdrawal delay
+fn queue_token_vault_withdrawal(game_state: &mut GameState, token_table: &mut TokenTable, amount: u64) -> Result<()> {
+    require!(amount > 0, SpinWheelError::InvalidAmount);
+    require!(token_table.queued_withdrawal == 0, SpinWheelError::WithdrawalAlreadyQueued);
+    
+    let now = Clock::get()?.unix_timestamp;
+    game_state.refresh_timelock(now);
+    let unlocks_at = now
+        .checked_add(game_state.withdrawal_delay)
+        .ok_or(SpinWheelError::ArithmeticOverflow)?;
+    token_table.queued_withdrawal = amount;
+    token_table.withdrawal_unlocks_at = unlocks_at;
+    
+    emit!(WithdrawalQueued {
+        table_id: game_state.table_id,
+        mint: token_table.mint,
+        amount,
+        unlocks_at,
+    });
+    Ok(())
+}
+
+/// Moves lamports out of the house vault (only when paused, never below pending payouts)
+fn withdraw_lamports<'info>(
+    game_state: &GameState,
+    house_vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    require!(game_state.is_paused, SpinWheelError::GameNotPaused); // Added pause check
+    require!(amount > 0, SpinWheelError::InvalidAmount);
+    
+    let house_balance = house_vault.lamports();
+    let min_rent_balance = Rent::get()?.minimum_balance(8);
+    require!(
+        house_balance.saturating_sub(amount) >= min_rent_balance.saturating_add(game_state.reserved),
+        SpinWheelError::InsufficientHouseFunds
+    );
+    
+    **house_vault.try_borrow_mut_lamports()? -= amount;
+    **to.try_borrow_mut_lamports()? += amount;
+    
+    msg!("Withdrew {} lamports from house vault", amount);
+    Ok(())
+}
+
+/// Moves tokens out of a token table's vault (only when paused, never below pending payouts)
+fn withdraw_tokens<'info>(
+    game_state: &GameState,
+    token_table: &Account<'info, TokenTable>,
+    token_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<()> {
+    require!(game_state.is_paused, SpinWheelError::GameNotPaused);
+    require!(amount > 0, SpinWheelError::InvalidAmount);
+    require!(
+        token_vault.amount.saturating_sub(token_table.reserved) >= amount,
+        SpinWheelError::InsufficientHouseFunds
+    );
+    
+    let table_game_state = token_table.game_state;
+    let table_mint = token_table.mint;
+    let seeds: &[&[u8]] = &[b"token_table", table_game_state.as_ref(), table_mint.as_ref(), &[token_table.bump]];
+    token_interface::transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: token_vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: token_table.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+        mint.decimals,
+    )?;
+    
+    msg!("Withdrew {} from token vault", amount);
+    Ok(())
+}
+
+/// Checks that `executor` is an admin and the proposal has enough current approvals; returns the approval count
+fn check_approved(multisig: &Multisig, proposal: &Proposal, executor: &Pubkey) -> Result<u8> {
+    multisig.admin_index(executor)?;
+    require!(proposal.generation == multisig.generation, SpinWheelError::StaleProposal);
+    let approvals = proposal.approvals.count_ones() as u8;
+    require!(approvals >= multisig.threshold, SpinWheelError::NotEnoughApprovals);
+    Ok(approvals)
+}
+
+/// 1 to MAX_ADMINS distinct admins, with a threshold any of them can reach
+fn validate_admins(admins: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(
+        (1..=MAX_ADMINS).contains(&admins.len()) && threshold >= 1 && threshold as usize <= admins.len(),
+        SpinWheelError::InvalidMultisig
+    );
+    for (i, admin) in admins.iter().enumerate() {
+        require!(!admins[..i].contains(admin), SpinWheelError::InvalidMultisig);
+    }
+    Ok(())
+}
+
+/// Winning payout: the segment multiplier times the bet, less the house edge percentage
+fn win_payout(bet_amount: u64, multiplier: u16, house_edge: u8) -> Result<u64> {
+    let base_payout = bet_amount
+        .checked_mul(multiplier as u64)
+        .ok_or(SpinWheelError::ArithmeticOverflow)?;
+    let house_cut = base_payout
+        .checked_mul(house_edge as u64)
+        .ok_or(SpinWheelError::ArithmeticOverflow)?
+        / 100;
+    let payout = base_payout
+        .checked_sub(house_cut)
+        .ok_or(SpinWheelError::ArithmeticOverflow)?;
+    Ok(payout)
+}
+
+/// Mixes both seeds and the spin slot hash into a weighted draw over the wheel's segments.
+/// Public so clients can recompute a fulfilled spin from the SpinResult event.
+pub fn derive_spin_result(
+    wheel_config: &WheelConfig,
+    house_seed: &[u8; 32],
+    player_seed: &[u8; 32],
+    slot_hash: &[u8; 32],
+) -> u8 {
+    let mixed = hashv(&[&house_seed[..], &player_seed[..], &slot_hash[..]]).to_bytes();
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&mixed[..8]);
+    let mut roll = u64::from_le_bytes(head) % wheel_config.total_weight as u64;
+    for (index, segment) in wheel_config.segments.iter().enumerate() {
+        if roll < segment.weight as u64 {
+            return index as u8;
+        }
+        roll -= segment.weight as u64;
+    }
+    // Unreachable while total_weight is the sum of the segment weights
+    (wheel_config.segments.len() - 1) as u8
+}
+
+/// Looks up the hash of `slot` in raw SlotHashes sysvar data.
+/// Layout: u64 entry count, then (u64 slot, [u8; 32] hash) entries, newest first.
+fn find_slot_hash(data: &[u8], slot: u64) -> Option<[u8; 32]> {
+    let mut count = [0u8; 8];
+    count.copy_from_slice(data.get(..8)?);
+    let entries = data.get(8..)?;
+    entries.chunks_exact(40).take(u64::from_le_bytes(count) as usize).find_map(|entry| {
+        let mut entry_slot = [0u8; 8];
+        entry_slot.copy_from_slice(&entry[..8]);
+        if u64::from_le_bytes(entry_slot) == slot {
+            let mut hash = [0u8; 32];
+            hash.copy_from_slice(&entry[8..]);
+            Some(hash)
+        } else {
+            None
+        }
+    })
+}
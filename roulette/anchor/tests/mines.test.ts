@@ -1,12 +1,19 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
-import { SystemProgram, PublicKey, Keypair } from "@solana/web3.js";
+import { SystemProgram, PublicKey, Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
 import { assert } from "chai";
 import crypto from "crypto";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createMint,
+  createTokenAccount,
+  createTransferFeeMint,
+  mintTo,
+  tokenBalance,
+} from "./token-utils";
 
-const MERKLE_DEPTH = 5; // must match program
-const MAX_BOARD_SIZE = 25;
-const TREE_LEAVES = 1 << MERKLE_DEPTH; // 32
+const HOUSE_EDGE_BPS = 100; // 1%
 
 // Helpers mirroring on-chain hashing
 const sha256 = (parts: Uint8Array[]) => {
@@ -32,11 +39,41 @@ const leafHash = (tileIndex: number, isMine: number, nonce32: Uint8Array) => {
   ]);
 };
 
-type Tree = { levels: Uint8Array[][] }; // levels[0] = leaves, up to root levels[MERKLE_DEPTH]
+// Mirrors the on-chain payout curve: bet * C(n, k) / C(n - mines, k) less the house edge
+const binomial = (n: bigint, k: bigint) => {
+  let acc = 1n;
+  for (let i = 0n; i < k; i++) acc = (acc * (n - i)) / (i + 1n);
+  return acc;
+};
+const DEFAULT_MAX_PAYOUT = 100 * LAMPORTS_PER_SOL;
+const payoutFor = (
+  bet: number,
+  tiles: number,
+  numMines: number,
+  safeRevealed: number,
+  edgeBps: number,
+  cap = DEFAULT_MAX_PAYOUT
+) => {
+  const n = BigInt(tiles);
+  const k = BigInt(safeRevealed);
+  const payout =
+    (BigInt(bet) * binomial(n, k) * BigInt(10_000 - edgeBps)) / (binomial(n - BigInt(numMines), k) * 10_000n);
+  return Number(payout < BigInt(cap) ? payout : BigInt(cap));
+};
+
+// Leaf nonces come from one board seed so the house can open every leaf in finalize_board
+const leafNonce = (boardSeed: Uint8Array, tileIndex: number) =>
+  sha256([new TextEncoder().encode("nonce"), boardSeed, leU32(tileIndex)]);
+
+// Leaves are padded to a power of two, so the depth is ceil(log2(tiles)) (must match program)
+const merkleDepth = (tiles: number) => Math.ceil(Math.log2(tiles));
+
+type Tree = { levels: Uint8Array[][] }; // levels[0] = leaves, up to root levels[depth]
 function buildTree(leaves: Uint8Array[]): Tree {
-  if (leaves.length !== TREE_LEAVES) throw new Error("bad leaf count");
+  const depth = Math.log2(leaves.length);
+  if (!Number.isInteger(depth)) throw new Error("bad leaf count");
   const levels: Uint8Array[][] = [leaves];
-  for (let d = 0; d < MERKLE_DEPTH; d++) {
+  for (let d = 0; d < depth; d++) {
     const prev = levels[d];
     const next: Uint8Array[] = [];
     for (let i = 0; i < prev.length; i += 2) {
@@ -52,7 +89,7 @@ function getProof(index: number, tree: Tree): { proof: Uint8Array[]; pathBits: n
   let idx = index;
   const proof: Uint8Array[] = [];
   let pathBits = 0;
-  for (let d = 0; d < MERKLE_DEPTH; d++) {
+  for (let d = 0; d < tree.levels.length - 1; d++) {
     const isRight = (idx & 1) === 1;
     const sibling = tree.levels[d][isRight ? idx - 1 : idx + 1];
     proof.push(sibling);
@@ -62,70 +99,278 @@ function getProof(index: number, tree: Tree): { proof: Uint8Array[]; pathBits: n
   return { proof, pathBits };
 }
 
+// Siblings needed to prove several leaves at once, level by level and left to right (must match program)
+function getMultiProof(indices: number[], tree: Tree): Uint8Array[] {
+  const nodes: Uint8Array[] = [];
+  let level = [...new Set(indices)].sort((a, b) => a - b);
+  for (let d = 0; d < tree.levels.length - 1; d++) {
+    const next: number[] = [];
+    for (let i = 0; i < level.length; i++) {
+      const idx = level[i];
+      if (level[i + 1] === (idx ^ 1)) i++;
+      else nodes.push(tree.levels[d][idx ^ 1]);
+      next.push(idx >> 1);
+    }
+    level = next;
+  }
+  return nodes;
+}
+
 describe("mines (merkle commit)", () => {
   const provider = anchor.AnchorProvider.env();
   anchor.setProvider(provider);
   const program = anchor.workspace.Mines as Program<any>;
 
+  const [bankrollPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("bankroll")],
+    program.programId
+  );
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("mines_config")],
+    program.programId
+  );
+
+  // Test wallet is both the player and the configured house
+  const house = provider.wallet.publicKey;
+  const player = provider.wallet.publicKey;
   let gameKp: Keypair;
-  let vaultPda: PublicKey;
-  let house: PublicKey;
-  let player: PublicKey;
 
-  // Game plan: choose 5 mines positions; reveal a safe tile and cash out original bet
-  const betLamports = new BN(5);
+  // Build leaves for every slot of the padded tree; slots past the board are safe fillers.
+  type Board = ReturnType<typeof buildBoard>;
+  const buildBoard = (mineIndices: number[], rows = 5, cols = 5) => {
+    const seed = crypto.randomBytes(32);
+    const tiles = rows * cols;
+    const mines = mineIndices.reduce((bits, i) => bits | (1n << BigInt(i)), 0n);
+    const isMine = (i: number) => Number((mines >> BigInt(i)) & 1n);
+    const nonces = Array.from({ length: 1 << merkleDepth(tiles) }, (_, i) => leafNonce(seed, i));
+    const tree = buildTree(nonces.map((nonce, i) => leafHash(i, isMine(i), nonce)));
+    return { seed, rows, cols, tiles, numMines: mineIndices.length, mines, isMine, nonces, tree, root: tree.levels[tree.levels.length - 1][0] };
+  };
+
+  // Game plan: choose 5 mines positions; reveal a safe tile and cash out at the curve multiplier
+  const betLamports = new BN(1_000);
   const numMines = 5;
-  const isMineByIndex: number[] = Array(MAX_BOARD_SIZE).fill(0);
-  for (let i = 0; i < numMines; i++) isMineByIndex[i] = 1; // first 5 are mines, rest safe
-  const nonces: Uint8Array[] = Array(TREE_LEAVES)
-    .fill(null)
-    .map(() => crypto.randomBytes(32));
-
-  // Build leaves for 32 slots. For 0..24, use real tiles; 25..31 fillers as safe with fixed nonces.
-  const leaves: Uint8Array[] = [];
-  for (let i = 0; i < TREE_LEAVES; i++) {
-    if (i < MAX_BOARD_SIZE) {
-      leaves.push(leafHash(i, isMineByIndex[i], nonces[i]));
-    } else {
-      // filler leaves
-      leaves.push(leafHash(i, 0, nonces[i]));
-    }
+  const board = buildBoard([0, 1, 2, 3, 4]); // first 5 are mines, rest safe
+  const { nonces, tree, root } = board;
+
+  async function revealTile(kp: Keypair, index: number, b: Board = board) {
+    const { proof, pathBits } = getProof(index, b.tree);
+    await program.methods
+      .revealTile(
+        index,
+        b.isMine(index),
+        Array.from(b.nonces[index]),
+        proof.map((p) => Array.from(p)),
+        pathBits
+      )
+      .accounts({ game: kp.publicKey, player })
+      .rpc();
   }
-  const tree = buildTree(leaves);
-  const root = tree.levels[MERKLE_DEPTH][0];
 
-  it("starts a game with merkle commitment", async () => {
-    gameKp = Keypair.generate();
-    const [vault, bump] = PublicKey.findProgramAddressSync(
-      [Buffer.from("vault"), gameKp.publicKey.toBuffer()],
-      program.programId
-    );
-    vaultPda = vault;
+  const openings = (b: Board, indices: number[]) =>
+    indices.map((i) => ({ tileIndex: i, isMine: b.isMine(i), leafNonce: Array.from(b.nonces[i]) }));
 
-    house = provider.wallet.publicKey; // test uses wallet as house
-    player = provider.wallet.publicKey;
+  async function finalizeBoard(kp: Keypair, b: Board = board) {
+    await program.methods
+      .finalizeBoard(Array.from(b.seed), new BN(b.mines.toString()))
+      .accounts({ game: kp.publicKey, bankroll: bankrollPda, player, house })
+      .rpc();
+  }
+
+  async function updateSettings(settings: {
+    house?: PublicKey;
+    minBet?: number;
+    isPaused?: boolean;
+    minExpirySecs?: number;
+    abandonGraceSecs?: number;
+    maxPayout?: number;
+    revealResponseSecs?: number;
+  }) {
+    const bn = (n?: number) => (n === undefined ? null : new BN(n));
+    await program.methods
+      .updateSettings(
+        settings.house ?? null,
+        null,
+        bn(settings.minBet),
+        null,
+        null,
+        settings.isPaused ?? null,
+        bn(settings.minExpirySecs),
+        null,
+        bn(settings.abandonGraceSecs),
+        bn(settings.maxPayout),
+        bn(settings.revealResponseSecs)
+      )
+      .accounts({ config: configPda, authority: provider.wallet.publicKey })
+      .rpc();
+  }
 
+  async function startGame(
+    kp: Keypair,
+    bet: BN,
+    houseKey: PublicKey = house,
+    b: Board = board,
+    mines = numMines,
+    expirySecs = 600
+  ) {
     await program.methods
-      .startGame(betLamports, numMines, Array.from(root))
+      .startGame(bet, mines, b.rows, b.cols, Array.from(b.root), new BN(expirySecs))
       .accounts({
-        game: gameKp.publicKey,
-        vault: vaultPda,
+        game: kp.publicKey,
+        config: configPda,
+        bankroll: bankrollPda,
         player,
-        house,
+        house: houseKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([kp])
+      .rpc();
+  }
+
+  beforeAll(async () => {
+    await program.methods
+      .initializeBankroll()
+      .accounts({
+        bankroll: bankrollPda,
+        authority: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .fundBankroll(new BN(LAMPORTS_PER_SOL))
+      .accounts({
+        bankroll: bankrollPda,
+        funder: provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
-      .signers([gameKp])
       .rpc();
 
+    await program.methods
+      .initializeConfig(house, HOUSE_EDGE_BPS)
+      .accounts({
+        config: configPda,
+        bankroll: bankrollPda,
+        authority: provider.wallet.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    // Default minimum is 0.001 SOL; keep test stakes tiny so the bankroll covers full clears
+    await updateSettings({ minBet: 1_000 });
+  });
+
+  it("initializes the config with default limits", async () => {
+    const config = await program.account.minesConfig.fetch(configPda);
+    assert.equal(config.house.toString(), house.toString());
+    assert.equal(config.houseEdgeBps, HOUSE_EDGE_BPS);
+    assert.equal(config.maxBet.toNumber(), LAMPORTS_PER_SOL);
+    assert.equal(config.maxMines, 63);
+    assert.equal(config.maxPayout.toNumber(), DEFAULT_MAX_PAYOUT);
+    assert.isFalse(config.isPaused);
+  });
+
+  it("rejects a house that is not in the config", async () => {
+    try {
+      await startGame(Keypair.generate(), betLamports, Keypair.generate().publicKey);
+      assert.fail("should have rejected a self-appointed house");
+    } catch (e) {
+      assert.include(e.error.errorMessage, "House does not match the config");
+    }
+  });
+
+  it("rejects a root committed without the house signature", async () => {
+    // A player who builds their own board knows where the mines are
+    const forger = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(forger.publicKey, LAMPORTS_PER_SOL);
+    await provider.connection.confirmTransaction(sig);
+
+    const kp = Keypair.generate();
+    const forgedLeaves = nonces.map((nonce, i) => leafHash(i, 0, nonce));
+    const forgedRoot = buildTree(forgedLeaves).levels[merkleDepth(board.tiles)][0];
+    const tx = await program.methods
+      .startGame(betLamports, numMines, board.rows, board.cols, Array.from(forgedRoot), new BN(600))
+      .accounts({
+        game: kp.publicKey,
+        config: configPda,
+        bankroll: bankrollPda,
+        player: forger.publicKey,
+        house,
+        systemProgram: SystemProgram.programId,
+      })
+      .transaction();
+    tx.feePayer = forger.publicKey;
+    tx.recentBlockhash = (await provider.connection.getLatestBlockhash()).blockhash;
+    tx.partialSign(forger, kp);
+
+    try {
+      await provider.connection.sendRawTransaction(tx.serialize({ requireAllSignatures: false }));
+      assert.fail("should have rejected a game the house did not sign");
+    } catch (e) {
+      assert.notInclude(String(e), "should have rejected");
+    }
+    assert.isNull(await provider.connection.getAccountInfo(kp.publicKey));
+  });
+
+  it("enforces the pause flag and bet limits", async () => {
+    await updateSettings({ isPaused: true });
+    try {
+      await startGame(Keypair.generate(), betLamports);
+      assert.fail("should have rejected a game on a paused table");
+    } catch (e) {
+      assert.include(e.error.errorMessage, "Game is currently paused");
+    } finally {
+      await updateSettings({ isPaused: false });
+    }
+
+    try {
+      await startGame(Keypair.generate(), new BN(999));
+      assert.fail("should have rejected a bet below the minimum");
+    } catch (e) {
+      assert.include(e.error.errorMessage, "Bet amount is too low");
+    }
+  });
+
+  it("rejects a game the bankroll cannot cover", async () => {
+    try {
+      // Clearing the board with 5 mines pays ~52,600x the stake
+      await startGame(Keypair.generate(), new BN(LAMPORTS_PER_SOL / 10));
+      assert.fail("should have rejected an uncovered game");
+    } catch (e) {
+      assert.include(e.error.errorMessage, "Bankroll cannot cover the maximum payout");
+    }
+  });
+
+  it("starts a game with merkle commitment", async () => {
+    gameKp = Keypair.generate();
+    const initialBankroll = await provider.connection.getBalance(bankrollPda);
+
+    await startGame(gameKp, betLamports);
+
     const game = await program.account.minesGame.fetch(gameKp.publicKey);
     assert.isTrue(game.isActive);
     assert.equal(game.numMines, numMines);
     assert.equal(game.betAmount.toNumber(), betLamports.toNumber());
+    assert.equal(game.houseEdgeBps, HOUSE_EDGE_BPS);
+    assert.equal(game.version, 2);
+
+    // The stake goes to the bankroll, which holds back the payout for clearing all 20 safe tiles
+    const finalBankroll = await provider.connection.getBalance(bankrollPda);
+    assert.equal(finalBankroll - initialBankroll, betLamports.toNumber());
+    // plus the penalty (one stake) for a board the house cannot open honestly
+    const maxPayout = payoutFor(betLamports.toNumber(), board.tiles, numMines, board.tiles - numMines, HOUSE_EDGE_BPS);
+    assert.equal(game.reserved.toNumber(), maxPayout + betLamports.toNumber());
+    const bankroll = await program.account.bankroll.fetch(bankrollPda);
+    assert.equal(bankroll.reserved.toNumber(), game.reserved.toNumber());
   });
 
   it("reveals a safe tile with merkle proof", async () => {
     const safeIndex = 10; // we set first 5 as mines; 10 is safe
     const { proof, pathBits } = getProof(safeIndex, tree);
+    let revealed: any = null;
+    const listener = program.addEventListener("tileRevealed", (event) => {
+      revealed = event;
+    });
 
     await program.methods
       .revealTile(
@@ -142,30 +387,768 @@ describe("mines (merkle commit)", () => {
       .rpc();
 
     const game = await program.account.minesGame.fetch(gameKp.publicKey);
-    assert.isTrue(game.revealedTiles[safeIndex]);
+    assert.equal(game.revealed.toString(), (1n << BigInt(safeIndex)).toString());
     assert.isFalse(game.lost);
     assert.isTrue(game.isActive);
     assert.equal(game.revealedCount, 1);
+
+    await new Promise((resolve) => setTimeout(resolve, 1000));
+    await program.removeEventListener(listener);
+    assert.isNotNull(revealed);
+    assert.equal(revealed.tileIndex, safeIndex);
+    assert.isFalse(revealed.isMine);
+    assert.equal(revealed.player.toString(), player.toString());
   });
 
-  it("cashes out bet back to player", async () => {
+  it("cashes out at the multiplier for one safe pick", async () => {
+    const expectedPayout = payoutFor(betLamports.toNumber(), board.tiles, numMines, 1, HOUSE_EDGE_BPS);
+    assert.equal(expectedPayout, 1_237); // 25/20 * 0.99
+    const initialBankroll = await provider.connection.getBalance(bankrollPda);
+
     await program.methods
       .cashOut()
       .accounts({
         game: gameKp.publicKey,
-        vault: vaultPda,
+        bankroll: bankrollPda,
         player,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
 
+    const finalBankroll = await provider.connection.getBalance(bankrollPda);
+    assert.equal(initialBankroll - finalBankroll, expectedPayout);
+
+    // Only the bad-board penalty stays reserved until the house opens the board
+    const game = await program.account.minesGame.fetch(gameKp.publicKey);
+    assert.isFalse(game.isActive);
+    assert.equal(game.reserved.toNumber(), betLamports.toNumber());
+    const bankroll = await program.account.bankroll.fetch(bankrollPda);
+    assert.equal(bankroll.reserved.toNumber(), betLamports.toNumber());
+  });
+
+  it("finalizes an honest board and closes the game", async () => {
+    const initialBankroll = await provider.connection.getBalance(bankrollPda);
+
+    await finalizeBoard(gameKp);
+
+    assert.isNull(await provider.connection.getAccountInfo(gameKp.publicKey));
+    assert.equal(await provider.connection.getBalance(bankrollPda), initialBankroll);
+    const bankroll = await program.account.bankroll.fetch(bankrollPda);
+    assert.equal(bankroll.reserved.toNumber(), 0);
+  });
+
+  it("refunds the stake plus a penalty when the board has the wrong mine count", async () => {
+    // The house commits a board where every tile is a mine while claiming 5
+    const rigged = buildBoard(Array.from({ length: board.tiles }, (_, i) => i));
+    const kp = Keypair.generate();
+    await startGame(kp, betLamports, house, rigged, numMines);
+    await revealTile(kp, 10, rigged);
+
+    const lost = await program.account.minesGame.fetch(kp.publicKey);
+    assert.isTrue(lost.lost);
+
+    const initialBankroll = await provider.connection.getBalance(bankrollPda);
+    await finalizeBoard(kp, rigged);
+
+    const finalBankroll = await provider.connection.getBalance(bankrollPda);
+    assert.equal(initialBankroll - finalBankroll, 2 * betLamports.toNumber());
+    assert.isNull(await provider.connection.getAccountInfo(kp.publicKey));
+  });
+
+  it("rejects finalizing a game that is still in play", async () => {
+    const kp = Keypair.generate();
+    await startGame(kp, betLamports);
+
     try {
-      const game = await program.account.minesGame.fetch(gameKp.publicKey);
-      // Depending on runtime, account may already be closed; if not, it should be inactive
-      assert.isFalse(game.isActive);
+      await finalizeBoard(kp);
+      assert.fail("should have rejected finalizing an active game");
     } catch (e) {
-      // If fetch fails, the account was closed as expected
-      assert.isTrue(true);
+      assert.include(e.error.errorMessage, "Game is not waiting for the board");
     }
   });
+
+  describe("board sizes", () => {
+    for (const [rows, cols, mineIndices] of [
+      [3, 3, [0]],
+      [6, 6, [0, 35]],
+      [8, 8, [63]],
+    ] as [number, number, number[]][]) {
+      it(`plays a ${rows}x${cols} board at its own payout curve`, async () => {
+        const b = buildBoard(mineIndices, rows, cols);
+        const kp = Keypair.generate();
+        await startGame(kp, betLamports, house, b, b.numMines);
+
+        const safeIndex = b.tiles - 2;
+        await revealTile(kp, safeIndex, b);
+        const game = await program.account.minesGame.fetch(kp.publicKey);
+        assert.equal(game.rows, rows);
+        assert.equal(game.cols, cols);
+        assert.equal(game.revealed.toString(), (1n << BigInt(safeIndex)).toString());
+
+        const initialBankroll = await provider.connection.getBalance(bankrollPda);
+        await program.methods
+          .cashOut()
+          .accounts({ game: kp.publicKey, bankroll: bankrollPda, player, systemProgram: SystemProgram.programId })
+          .rpc();
+        const finalBankroll = await provider.connection.getBalance(bankrollPda);
+        assert.equal(
+          initialBankroll - finalBankroll,
+          payoutFor(betLamports.toNumber(), b.tiles, b.numMines, 1, HOUSE_EDGE_BPS)
+        );
+
+        await finalizeBoard(kp, b);
+        assert.isNull(await provider.connection.getAccountInfo(kp.publicKey));
+      });
+    }
+
+    it("caps the reservation and payout of a board whose full clear overflows", async () => {
+      // Clearing 32 safe tiles of an 8x8 board with 32 mines pays ~1.8e18x the stake
+      const cap = 100_000;
+      const b = buildBoard(Array.from({ length: 32 }, (_, i) => i), 8, 8);
+      const kp = Keypair.generate();
+      await updateSettings({ maxPayout: cap });
+      try {
+        await startGame(kp, betLamports, house, b, b.numMines);
+        const started = await program.account.minesGame.fetch(kp.publicKey);
+        assert.equal(started.reserved.toNumber(), cap + betLamports.toNumber());
+
+        // Seven safe picks are worth ~183x the stake, past the cap
+        const picks = [32, 33, 34, 35, 36, 37, 38];
+        assert.isAbove(payoutFor(betLamports.toNumber(), b.tiles, b.numMines, picks.length, HOUSE_EDGE_BPS), cap);
+        await program.methods
+          .revealTiles(
+            picks.map((i) => ({ tileIndex: i, isMine: 0, leafNonce: Array.from(b.nonces[i]) })),
+            getMultiProof(picks, b.tree).map((n) => Array.from(n))
+          )
+          .accounts({ game: kp.publicKey, player })
+          .rpc();
+
+        const initialBankroll = await provider.connection.getBalance(bankrollPda);
+        await program.methods
+          .cashOut()
+          .accounts({ game: kp.publicKey, bankroll: bankrollPda, player, systemProgram: SystemProgram.programId })
+          .rpc();
+        assert.equal(initialBankroll - (await provider.connection.getBalance(bankrollPda)), cap);
+
+        await finalizeBoard(kp, b);
+        assert.isNull(await provider.connection.getAccountInfo(kp.publicKey));
+
+        try {
+          await startGame(Keypair.generate(), new BN(cap + 1), house, b, b.numMines);
+          assert.fail("should have rejected a stake above the payout cap");
+        } catch (e) {
+          assert.include(e.error.errorMessage, "Payout exceeds the table's max payout");
+        }
+      } finally {
+        await updateSettings({ maxPayout: DEFAULT_MAX_PAYOUT });
+      }
+    });
+
+    it("rejects boards larger than 8x8", async () => {
+      try {
+        await startGame(Keypair.generate(), betLamports, house, { ...board, rows: 9, cols: 9 });
+        assert.fail("should have rejected a 9x9 board");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Board must be between 2x2 and 8x8");
+      }
+    });
+
+    it("rejects a proof that does not match the board depth", async () => {
+      const kp = Keypair.generate();
+      await startGame(kp, betLamports);
+      const { proof, pathBits } = getProof(10, tree);
+
+      try {
+        await program.methods
+          .revealTile(10, 0, Array.from(nonces[10]), proof.slice(1).map((p) => Array.from(p)), pathBits)
+          .accounts({ game: kp.publicKey, player })
+          .rpc();
+        assert.fail("should have rejected a short proof");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Merkle proof length does not match the board");
+      }
+    });
+  });
+
+  describe("batch reveal", () => {
+    async function revealTiles(kp: Keypair, indices: number[], b: Board = board) {
+      await program.methods
+        .revealTiles(openings(b, indices), getMultiProof(indices, b.tree).map((n) => Array.from(n)))
+        .accounts({ game: kp.publicKey, player })
+        .rpc();
+    }
+
+    it("reveals several safe tiles with one deduplicated proof", async () => {
+      const kp = Keypair.generate();
+      await startGame(kp, betLamports);
+
+      const picks = [10, 11, 12, 13];
+      // Four separate proofs would carry 20 hashes; neighbours share most of them
+      assert.isBelow(getMultiProof(picks, tree).length, 5 * picks.length);
+      await revealTiles(kp, picks);
+
+      const game = await program.account.minesGame.fetch(kp.publicKey);
+      assert.isTrue(game.isActive);
+      assert.equal(game.revealedCount, picks.length);
+      const bits = picks.reduce((acc, i) => acc | (1n << BigInt(i)), 0n);
+      assert.equal(game.revealed.toString(), bits.toString());
+    });
+
+    it("stops at the first mine", async () => {
+      const kp = Keypair.generate();
+      await startGame(kp, betLamports);
+
+      // Tile 2 is a mine; tile 20 comes after it and stays hidden
+      await revealTiles(kp, [15, 2, 20]);
+
+      const game = await program.account.minesGame.fetch(kp.publicKey);
+      assert.isTrue(game.lost);
+      assert.isFalse(game.isActive);
+      assert.equal(game.revealedCount, 2);
+      assert.equal(game.revealed.toString(), ((1n << 15n) | (1n << 2n)).toString());
+    });
+
+    it("rejects a tampered leaf and a repeated tile", async () => {
+      const kp = Keypair.generate();
+      await startGame(kp, betLamports);
+
+      try {
+        const tampered = openings(board, [3, 10]).map((o) => ({ ...o, isMine: 0 }));
+        await program.methods
+          .revealTiles(tampered, getMultiProof([3, 10], tree).map((n) => Array.from(n)))
+          .accounts({ game: kp.publicKey, player })
+          .rpc();
+        assert.fail("should have rejected a mine presented as safe");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Commitment mismatch");
+      }
+
+      try {
+        await revealTiles(kp, [10, 10]);
+        assert.fail("should have rejected a repeated tile");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Tile already revealed");
+      }
+    });
+  });
+
+  describe("timeouts", () => {
+    it("rejects an expiry outside the config bounds", async () => {
+      try {
+        await startGame(Keypair.generate(), betLamports, house, board, numMines, 10);
+        assert.fail("should have rejected a 10 second expiry");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Expiry is outside the configured bounds");
+      }
+    });
+
+    it("lets the house settle an abandoned game at its cash-out value", async () => {
+      await updateSettings({ minExpirySecs: 1, abandonGraceSecs: 1 });
+      try {
+        const kp = Keypair.generate();
+        await startGame(kp, betLamports, house, board, numMines, 2);
+        await revealTile(kp, 10);
+
+        const settle = () =>
+          program.methods
+            .settleAbandoned()
+            .accounts({ game: kp.publicKey, config: configPda, bankroll: bankrollPda, player, house })
+            .rpc();
+
+        try {
+          await settle();
+          assert.fail("should have rejected settling inside the grace period");
+        } catch (e) {
+          assert.include(e.error.errorMessage, "Game not expired yet");
+        }
+
+        // expiry (2s) + grace (1s), with slack for the validator clock
+        await new Promise((resolve) => setTimeout(resolve, 5000));
+        const initialBankroll = await provider.connection.getBalance(bankrollPda);
+        await settle();
+
+        const finalBankroll = await provider.connection.getBalance(bankrollPda);
+        assert.equal(
+          initialBankroll - finalBankroll,
+          payoutFor(betLamports.toNumber(), board.tiles, numMines, 1, HOUSE_EDGE_BPS)
+        );
+        const game = await program.account.minesGame.fetch(kp.publicKey);
+        assert.isFalse(game.isActive);
+        assert.equal(game.reserved.toNumber(), betLamports.toNumber());
+
+        await finalizeBoard(kp);
+        assert.isNull(await provider.connection.getAccountInfo(kp.publicKey));
+      } finally {
+        await updateSettings({ minExpirySecs: 60, abandonGraceSecs: 600 });
+      }
+    });
+
+    it("rejects reveals and cash-outs after expiry", async () => {
+      await updateSettings({ minExpirySecs: 2 });
+      try {
+        const kp = Keypair.generate();
+        await startGame(kp, betLamports, house, board, numMines, 2);
+        await revealTile(kp, 10);
+        await new Promise((resolve) => setTimeout(resolve, 4000));
+
+        try {
+          await revealTile(kp, 11);
+          assert.fail("should have rejected a reveal after expiry");
+        } catch (e) {
+          assert.include(e.error.errorMessage, "Game has expired");
+        }
+        try {
+          await program.methods
+            .cashOut()
+            .accounts({ game: kp.publicKey, bankroll: bankrollPda, player, systemProgram: SystemProgram.programId })
+            .rpc();
+          assert.fail("should have rejected a cash-out after expiry");
+        } catch (e) {
+          assert.include(e.error.errorMessage, "Game has expired");
+        }
+      } finally {
+        await updateSettings({ minExpirySecs: 60 });
+      }
+    });
+
+    it("lets the house open a requested mine the player withholds", async () => {
+      const kp = Keypair.generate();
+      await startGame(kp, betLamports);
+      await revealTile(kp, 10);
+      await program.methods
+        .requestTiles(new BN(1 << 2))
+        .accounts({ game: kp.publicKey, player })
+        .rpc();
+
+      // The player has seen tile 2 is a mine and tries to leave with the cash-out value
+      try {
+        await program.methods
+          .cashOut()
+          .accounts({ game: kp.publicKey, bankroll: bankrollPda, player, systemProgram: SystemProgram.programId })
+          .rpc();
+        assert.fail("should have rejected a cash-out with a tile pending");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "A requested tile has not been opened yet");
+      }
+
+      const houseReveal = (indices: number[]) =>
+        program.methods
+          .houseRevealTiles(openings(board, indices), getMultiProof(indices, tree).map((n) => Array.from(n)))
+          .accounts({ game: kp.publicKey, house })
+          .rpc();
+      try {
+        await houseReveal([3]);
+        assert.fail("should have rejected opening a tile the player did not request");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Tile was not requested by the player");
+      }
+
+      await houseReveal([2]);
+      const game = await program.account.minesGame.fetch(kp.publicKey);
+      assert.isTrue(game.lost);
+      assert.isFalse(game.isActive);
+      assert.equal(game.requested.toNumber(), 0);
+    });
+
+    it("refunds the stake only once the house leaves a request unanswered", async () => {
+      await updateSettings({ revealResponseSecs: 1 });
+      try {
+        const kp = Keypair.generate();
+        await startGame(kp, betLamports);
+        const abort = () =>
+          program.methods
+            .abortRefund()
+            .accounts({
+              game: kp.publicKey,
+              config: configPda,
+              bankroll: bankrollPda,
+              player,
+              systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+
+        try {
+          await abort();
+          assert.fail("should have rejected aborting a game the house is still serving");
+        } catch (e) {
+          assert.include(e.error.errorMessage, "Game not expired yet");
+        }
+
+        await program.methods
+          .requestTiles(new BN(1 << 10))
+          .accounts({ game: kp.publicKey, player })
+          .rpc();
+        await new Promise((resolve) => setTimeout(resolve, 3000));
+
+        const initialBankroll = await provider.connection.getBalance(bankrollPda);
+        await abort();
+        assert.equal(initialBankroll - (await provider.connection.getBalance(bankrollPda)), betLamports.toNumber());
+        assert.isNull(await provider.connection.getAccountInfo(kp.publicKey));
+      } finally {
+        await updateSettings({ revealResponseSecs: 120 });
+      }
+    });
+  });
+
+  describe("layout migration", () => {
+    // Preloaded by Anchor.toml from tests/fixtures: an active 5x5 game written by the v1 program,
+    // with 5 mines and tile 10 revealed, and its vault PDA holding the 1,000 lamport stake plus rent
+    const v1Game = new PublicKey("3ECWFLeSB2uMuPpmP2zSe7atmKzhnpFzw2VenWPG8wYp");
+    const v1Player = new PublicKey("3rvXS3rwnvQr5fRygaKPL4bzMJ1Y93nc2hyBZUd3ws2Q");
+    const v1Stake = 1_000;
+    const [v1Vault] = PublicKey.findProgramAddressSync([Buffer.from("vault"), v1Game.toBuffer()], program.programId);
+
+    const migrate = (game: PublicKey, gamePlayer: PublicKey = player) =>
+      program.methods
+        .migrateGame()
+        .accounts({
+          game,
+          vault: PublicKey.findProgramAddressSync([Buffer.from("vault"), game.toBuffer()], program.programId)[0],
+          config: configPda,
+          bankroll: bankrollPda,
+          player: gamePlayer,
+          payer: provider.wallet.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    it("migrates a v1 game and moves its vault stake into the bankroll", async () => {
+      const before = await provider.connection.getAccountInfo(v1Game);
+      assert.equal(before.data.length, 8 + 175);
+      const vaultBalance = await provider.connection.getBalance(v1Vault);
+      const initialBankroll = await provider.connection.getBalance(bankrollPda);
+      const initialReserved = (await program.account.bankroll.fetch(bankrollPda)).reserved.toNumber();
+
+      await migrate(v1Game, v1Player);
+
+      // The payer tops up the rent for the larger layout
+      const after = await provider.connection.getAccountInfo(v1Game);
+      const size = program.account.minesGame.size; // includes the discriminator
+      assert.equal(after.data.length, size);
+      assert.equal(after.lamports, await provider.connection.getMinimumBalanceForRentExemption(size));
+
+      const game = await program.account.minesGame.fetch(v1Game);
+      assert.equal(game.version, 2);
+      assert.isTrue(game.isActive);
+      assert.isTrue(game.legacyBoard);
+      assert.equal(game.player.toString(), v1Player.toString());
+      assert.equal(game.betAmount.toNumber(), v1Stake);
+      assert.equal(game.numMines, 5);
+      assert.equal(game.rows, 5);
+      assert.equal(game.cols, 5);
+      assert.equal(game.revealed.toString(), (1n << 10n).toString());
+      assert.equal(game.revealedCount, 1);
+      assert.equal(game.houseEdgeBps, HOUSE_EDGE_BPS);
+      assert.equal(game.mint.toString(), PublicKey.default.toString());
+
+      // The stake joins the bankroll, which now backs a full clear; the vault's rent goes to the player
+      assert.isNull(await provider.connection.getAccountInfo(v1Vault));
+      assert.equal(await provider.connection.getBalance(bankrollPda), initialBankroll + v1Stake);
+      assert.equal(await provider.connection.getBalance(v1Player), vaultBalance - v1Stake);
+      const maxPayout = payoutFor(v1Stake, 25, 5, 20, HOUSE_EDGE_BPS);
+      assert.equal(game.reserved.toNumber(), maxPayout);
+      const bankroll = await program.account.bankroll.fetch(bankrollPda);
+      assert.equal(bankroll.reserved.toNumber(), initialReserved + maxPayout);
+    });
+
+    it("refuses to migrate a game that is already on v2", async () => {
+      const kp = Keypair.generate();
+      await startGame(kp, betLamports);
+
+      // A v2 game has no vault, so it fails on the vault before its layout is even read
+      try {
+        await migrate(kp.publicKey);
+        assert.fail("should have rejected a v2 account");
+      } catch (e) {
+        assert.notInclude(String(e), "should have rejected");
+      }
+
+      const game = await program.account.minesGame.fetch(kp.publicKey);
+      assert.equal(game.version, 2);
+    });
+  });
+
+  describe("token bets", () => {
+    const payer = (provider.wallet as anchor.Wallet).payer;
+    let mint: PublicKey;
+    let tokenBankrollPda: PublicKey;
+    let tokenVaultPda: PublicKey;
+    let playerTokens: PublicKey;
+    const tokenAccounts = () => ({
+      tokenBankroll: tokenBankrollPda,
+      bankrollTokenAccount: tokenVaultPda,
+      playerTokenAccount: playerTokens,
+      mint,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+
+    beforeAll(async () => {
+      mint = await createMint(provider, payer);
+      [tokenBankrollPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_bankroll"), mint.toBuffer()],
+        program.programId
+      );
+      [tokenVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_vault"), mint.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeTokenBankroll(new BN(1_000), new BN(1_000_000), new BN(100_000_000))
+        .accounts({
+          tokenBankroll: tokenBankrollPda,
+          tokenVault: tokenVaultPda,
+          mint,
+          bankroll: bankrollPda,
+          authority: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      // The wallet funds the bankroll and plays from the same token account
+      playerTokens = await createTokenAccount(provider, payer, mint, player);
+      await mintTo(provider, payer, mint, playerTokens, 1_000_000_000);
+      await program.methods
+        .fundTokenBankroll(new BN(500_000_000))
+        .accounts({
+          tokenBankroll: tokenBankrollPda,
+          tokenVault: tokenVaultPda,
+          mint,
+          funderTokenAccount: playerTokens,
+          funder: provider.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    });
+
+    it("stakes, cashes out and finalizes a game in the whitelisted mint", async () => {
+      const kp = Keypair.generate();
+      const bet = new BN(1_000); // a full clear pays ~53,000x, so keep it small
+      const initialTokens = await tokenBalance(provider, playerTokens);
+
+      await program.methods
+        .startGame(bet, numMines, board.rows, board.cols, Array.from(board.root), new BN(600))
+        .accounts({
+          game: kp.publicKey,
+          config: configPda,
+          bankroll: bankrollPda,
+          player,
+          house,
+          token: tokenAccounts(),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([kp])
+        .rpc();
+
+      const started = await program.account.minesGame.fetch(kp.publicKey);
+      assert.equal(started.mint.toString(), mint.toString());
+      assert.equal(await tokenBalance(provider, playerTokens), initialTokens - bet.toNumber());
+      let tokenBankroll = await program.account.tokenBankroll.fetch(tokenBankrollPda);
+      assert.equal(tokenBankroll.reserved.toNumber(), started.reserved.toNumber());
+
+      await revealTile(kp, 10);
+      await program.methods
+        .cashOut()
+        .accounts({
+          game: kp.publicKey,
+          bankroll: bankrollPda,
+          player,
+          token: tokenAccounts(),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const payout = payoutFor(bet.toNumber(), board.tiles, numMines, 1, HOUSE_EDGE_BPS);
+      assert.equal(await tokenBalance(provider, playerTokens), initialTokens - bet.toNumber() + payout);
+
+      await program.methods
+        .finalizeBoard(Array.from(board.seed), new BN(board.mines.toString()))
+        .accounts({ game: kp.publicKey, bankroll: bankrollPda, player, house, token: tokenAccounts() })
+        .rpc();
+
+      tokenBankroll = await program.account.tokenBankroll.fetch(tokenBankrollPda);
+      assert.equal(tokenBankroll.reserved.toNumber(), 0);
+      assert.equal(tokenBankroll.totalWagered.toNumber(), bet.toNumber());
+      assert.equal(tokenBankroll.totalPaidOut.toNumber(), payout);
+    });
+
+    it("rejects settling a token game without its token accounts", async () => {
+      const kp = Keypair.generate();
+      await program.methods
+        .startGame(new BN(1_000), numMines, board.rows, board.cols, Array.from(board.root), new BN(600))
+        .accounts({
+          game: kp.publicKey,
+          config: configPda,
+          bankroll: bankrollPda,
+          player,
+          house,
+          token: tokenAccounts(),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([kp])
+        .rpc();
+      await revealTile(kp, 10);
+
+      try {
+        await program.methods
+          .cashOut()
+          .accounts({ game: kp.publicKey, bankroll: bankrollPda, player, systemProgram: SystemProgram.programId })
+          .rpc();
+        assert.fail("should have required the token accounts");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Token accounts are required");
+      }
+    });
+
+    it("nets a refunded token stake out of the wagered total", async () => {
+      await updateSettings({ revealResponseSecs: 1 });
+      try {
+        const kp = Keypair.generate();
+        const bet = new BN(1_000);
+        await program.methods
+          .startGame(bet, numMines, board.rows, board.cols, Array.from(board.root), new BN(600))
+          .accounts({
+            game: kp.publicKey,
+            config: configPda,
+            bankroll: bankrollPda,
+            player,
+            house,
+            token: tokenAccounts(),
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([kp])
+          .rpc();
+        await program.methods
+          .requestTiles(new BN(1 << 10))
+          .accounts({ game: kp.publicKey, player })
+          .rpc();
+        await new Promise((resolve) => setTimeout(resolve, 3000));
+
+        const before = await program.account.tokenBankroll.fetch(tokenBankrollPda);
+        let aborted: any = null;
+        const listener = program.addEventListener("gameAborted", (event) => {
+          aborted = event;
+        });
+        await program.methods
+          .abortRefund()
+          .accounts({
+            game: kp.publicKey,
+            config: configPda,
+            bankroll: bankrollPda,
+            player,
+            token: tokenAccounts(),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+
+        const after = await program.account.tokenBankroll.fetch(tokenBankrollPda);
+        assert.equal(before.totalWagered.toNumber() - after.totalWagered.toNumber(), bet.toNumber());
+        assert.equal(after.totalPaidOut.toNumber(), before.totalPaidOut.toNumber());
+
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+        await program.removeEventListener(listener);
+        assert.isNotNull(aborted);
+        assert.equal(aborted.mint.toString(), mint.toString());
+        assert.equal(aborted.refund.toNumber(), bet.toNumber());
+      } finally {
+        await updateSettings({ revealResponseSecs: 120 });
+      }
+    });
+
+    it("refuses to whitelist a mint that charges a transfer fee", async () => {
+      const feeMint = await createTransferFeeMint(provider, payer, 100, 1_000_000);
+      const [feeBankrollPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_bankroll"), feeMint.toBuffer()],
+        program.programId
+      );
+      const [feeVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_vault"), feeMint.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .initializeTokenBankroll(new BN(1_000), new BN(1_000_000), new BN(100_000_000))
+          .accounts({
+            tokenBankroll: feeBankrollPda,
+            tokenVault: feeVaultPda,
+            mint: feeMint,
+            bankroll: bankrollPda,
+            authority: provider.wallet.publicKey,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc();
+        assert.fail("should have rejected a transfer-fee mint");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Mints with a transfer fee are not supported");
+      }
+    });
+  });
+
+  describe("player limits", () => {
+    const [limitsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("limits"), player.toBuffer()],
+      program.programId
+    );
+
+    const setLimits = (maxBet: number) =>
+      program.methods
+        .setLimits({
+          currencies: [
+            { mint: PublicKey.default, dailyLossLimit: new BN(0), weeklyLossLimit: new BN(0), maxBet: new BN(maxBet) },
+          ],
+          sessionCooldown: new BN(0),
+          excludedUntil: new BN(0),
+        })
+        .accounts({ playerLimits: limitsPda, player, systemProgram: SystemProgram.programId })
+        .rpc();
+
+    it("credits a cash-out against the player's losses", async () => {
+      // Losses are tracked in the currencies the player lists
+      await setLimits(0);
+      const kp = Keypair.generate();
+      await startGame(kp, betLamports);
+      const staked = await program.account.playerLimits.fetch(limitsPda);
+      await revealTile(kp, 10);
+      await program.methods
+        .cashOut()
+        .accounts({
+          game: kp.publicKey,
+          bankroll: bankrollPda,
+          player,
+          playerLimits: limitsPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      const settled = await program.account.playerLimits.fetch(limitsPda);
+      const [stakedLoss, settledLoss] = [staked, settled].map((x) => x.limits.losses[0].dayLoss.toNumber());
+      assert.isBelow(settledLoss, stakedLoss);
+      assert.isAbove(settledLoss, stakedLoss - 2 * betLamports.toNumber());
+    });
+
+    it("caps single bets at once and delays lifting the cap", async () => {
+      await setLimits(betLamports.toNumber());
+      try {
+        await startGame(Keypair.generate(), betLamports.muln(2));
+        assert.fail("should have rejected a bet above the player's max bet");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Bet is above the player's max bet");
+      }
+
+      await setLimits(0);
+      const limits = await program.account.playerLimits.fetch(limitsPda);
+      assert.equal(limits.limits.settings.currencies[0].maxBet.toNumber(), betLamports.toNumber());
+      assert.equal(limits.limits.pending.currencies[0].maxBet.toNumber(), 0);
+      assert.isAbove(limits.limits.pendingAt.toNumber(), 0);
+      try {
+        await startGame(Keypair.generate(), betLamports.muln(2));
+        assert.fail("should have kept the cap until the delay passes");
+      } catch (e) {
+        assert.include(e.error.errorMessage, "Bet is above the player's max bet");
+      }
+    });
+  });
 });
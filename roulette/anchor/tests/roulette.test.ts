@@ -1,8 +1,39 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { Roulette } from "../target/types/roulette";
-import { PublicKey, Keypair, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { PublicKey, Keypair, LAMPORTS_PER_SOL, SYSVAR_SLOT_HASHES_PUBKEY } from "@solana/web3.js";
 import { expect } from "chai";
+import crypto from "crypto";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createMint,
+  createTokenAccount,
+  createTransferFeeMint,
+  mintTo,
+  tokenBalance,
+} from "./token-utils";
+
+// Helpers mirroring on-chain hashing
+const sha256 = (parts: Uint8Array[]) => {
+  const h = crypto.createHash("sha256");
+  parts.forEach((p) => h.update(Buffer.from(p)));
+  return new Uint8Array(h.digest());
+};
+const deriveSpinResult = (
+  variant: any,
+  houseSeed: number[],
+  playerSeed: number[],
+  slotHash: number[]
+) => {
+  const mixed = Buffer.from(
+    sha256([Uint8Array.from(houseSeed), Uint8Array.from(playerSeed), Uint8Array.from(slotHash)])
+  );
+  const pockets = "doubleZero" in variant ? 38 : 37;
+  return Number(mixed.readBigUInt64LE(0) % BigInt(pockets));
+};
+
+const RED_NUMBERS = [1, 3, 5, 7, 9, 12, 14, 16, 18, 19, 21, 23, 25, 27, 30, 32, 34, 36];
 
 describe("roulette", () => {
   // Configure the client to use the local cluster.
@@ -10,14 +41,79 @@ describe("roulette", () => {
 
   const program = anchor.workspace.Roulette as Program<Roulette>;
   const provider = anchor.getProvider();
+  // Test wallet acts as the house: it owns the vault and co-signs every bet
+  const house = (provider.wallet as anchor.Wallet).payer;
+
+  const [houseVaultPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("house_vault")],
+    program.programId
+  );
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("roulette_config")],
+    program.programId
+  );
+
+  // Each player has a single game account, reused for every spin
+  const gamePda = (player: PublicKey) =>
+    PublicKey.findProgramAddressSync([Buffer.from("roulette"), player.toBuffer()], program.programId)[0];
 
   let player: Keypair;
-  let gameAccount: Keypair;
+
+  beforeAll(async () => {
+    await program.methods
+      .initializeHouse()
+      .accounts({
+        houseVault: houseVaultPda,
+        authority: house.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .fundHouse(new anchor.BN(10 * LAMPORTS_PER_SOL))
+      .accounts({
+        houseVault: houseVaultPda,
+        funder: house.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    await program.methods
+      .initializeConfig({ singleZero: {} })
+      .accounts({
+        config: configPda,
+        houseVault: houseVaultPda,
+        authority: house.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  });
+
+  async function updateSettings(settings: {
+    minBet?: number;
+    maxBet?: number;
+    maxExposure?: number;
+    isPaused?: boolean;
+    variant?: any;
+    evenMoneyRule?: any;
+  }) {
+    const bn = (n?: number) => (n === undefined ? null : new anchor.BN(n));
+    await program.methods
+      .updateSettings(
+        bn(settings.minBet),
+        bn(settings.maxBet),
+        bn(settings.maxExposure),
+        settings.isPaused ?? null,
+        settings.variant ?? null,
+        settings.evenMoneyRule ?? null
+      )
+      .accounts({ config: configPda, authority: house.publicKey })
+      .rpc();
+  }
 
   beforeEach(async () => {
     // Create a new player for each test
     player = Keypair.generate();
-    gameAccount = Keypair.generate();
 
     // Airdrop SOL to player
     const signature = await provider.connection.requestAirdrop(
@@ -27,242 +123,385 @@ describe("roulette", () => {
     await provider.connection.confirmTransaction(signature);
   });
 
-  describe("Red/Black Bets", () => {
-    it("Should handle a winning red bet", async () => {
-      const betAmount = 0.1 * LAMPORTS_PER_SOL; // 0.1 SOL
-      
-      const initialPlayerBalance = await provider.connection.getBalance(player.publicKey);
-      
+  // The spin uses the hash of the bet slot, so settlement must land in a later slot
+  async function waitForNextSlot(slot: number) {
+    while ((await provider.connection.getSlot()) <= slot) {
+      await new Promise((resolve) => setTimeout(resolve, 200));
+    }
+  }
+
+  async function placeBet(
+    betAmount: number,
+    betType: any,
+    houseSeed: Uint8Array = crypto.randomBytes(32)
+  ) {
+    const commitment = sha256([houseSeed]);
+    const playerSeed = crypto.randomBytes(32);
+
+    await program.methods
+      .placeBet(
+        new anchor.BN(betAmount),
+        betType,
+        Array.from(commitment),
+        Array.from(playerSeed)
+      )
+      .accounts({
+        game: gamePda(player.publicKey),
+        config: configPda,
+        houseVault: houseVaultPda,
+        player: player.publicKey,
+        house: house.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([player, house])
+      .rpc();
+
+    return houseSeed;
+  }
+
+  async function placeBets(bets: { betType: any; amount: number }[]) {
+    const houseSeed = crypto.randomBytes(32);
+
+    await program.methods
+      .placeBets(
+        bets.map(({ betType, amount }) => ({ betType, amount: new anchor.BN(amount) })),
+        Array.from(sha256([houseSeed])),
+        Array.from(crypto.randomBytes(32))
+      )
+      .accounts({
+        game: gamePda(player.publicKey),
+        config: configPda,
+        houseVault: houseVaultPda,
+        player: player.publicKey,
+        house: house.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([player, house])
+      .rpc();
+
+    return houseSeed;
+  }
+
+  async function settleBet(houseSeed: Uint8Array) {
+    const game = gamePda(player.publicKey);
+    const placed = await program.account.rouletteGame.fetch(game);
+    await waitForNextSlot(placed.betSlot.toNumber());
+
+    await program.methods
+      .settleBet(Array.from(houseSeed))
+      .accounts({
+        game,
+        houseVault: houseVaultPda,
+        player: player.publicKey,
+        house: house.publicKey,
+        recentSlothashes: SYSVAR_SLOT_HASHES_PUBKEY,
+      })
+      .signers([house])
+      .rpc();
+
+    return program.account.rouletteGame.fetch(game);
+  }
+
+  async function playRoulette(betAmount: number, betType: any) {
+    const houseSeed = await placeBet(betAmount, betType);
+    return settleBet(houseSeed);
+  }
+
+  async function refundBet() {
+    await program.methods
+      .refundBet()
+      .accounts({
+        game: gamePda(player.publicKey),
+        houseVault: houseVaultPda,
+        player: player.publicKey,
+      })
+      .signers([player])
+      .rpc();
+  }
+
+  async function closeGame() {
+    await program.methods
+      .closeGame()
+      .accounts({ game: gamePda(player.publicKey), player: player.publicKey })
+      .signers([player])
+      .rpc();
+  }
+
+  describe("Commit-Reveal", () => {
+    it("Should store the commitment and leave the bet unsettled", async () => {
+      const betAmount = 0.05 * LAMPORTS_PER_SOL;
+      const houseSeed = crypto.randomBytes(32);
+
+      await placeBet(betAmount, { red: {} }, houseSeed);
+
+      const gameState = await program.account.rouletteGame.fetch(gamePda(player.publicKey));
+      expect(gameState.house.toString()).to.equal(house.publicKey.toString());
+      expect(Buffer.from(gameState.houseCommitment)).to.deep.equal(Buffer.from(sha256([houseSeed])));
+      expect(gameState.isFinished).to.be.false;
+    });
+
+    it("Should produce a spin anyone can recompute from the stored seeds", async () => {
+      const gameState = await playRoulette(0.05 * LAMPORTS_PER_SOL, { red: {} });
+
+      expect(gameState.isFinished).to.be.true;
+      expect(gameState.spinResult).to.equal(
+        deriveSpinResult(gameState.variant, gameState.houseSeed, gameState.playerSeed, gameState.slotHash)
+      );
+    });
+
+    it("Should reject a house seed that does not match the commitment", async () => {
+      await placeBet(0.05 * LAMPORTS_PER_SOL, { red: {} });
+
+      try {
+        await settleBet(crypto.randomBytes(32));
+        expect.fail("Should have rejected a forged house seed");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("House seed does not match commitment");
+      }
+    });
+
+    it("Should not allow a bet to be settled twice", async () => {
+      const houseSeed = await placeBet(0.05 * LAMPORTS_PER_SOL, { black: {} });
+      await settleBet(houseSeed);
+
+      try {
+        await settleBet(houseSeed);
+        expect.fail("Should have rejected a second settlement");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Bet already settled");
+      }
+    });
+
+    it("Should not refund while the settle window is open", async () => {
+      await placeBet(0.05 * LAMPORTS_PER_SOL, { red: {} });
+
+      try {
+        await refundBet();
+        expect.fail("Should have rejected an early refund");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Settle window is still open");
+      }
+    });
+  });
+
+  describe("House Vault", () => {
+    it("Should record the house authority", async () => {
+      const vault = await program.account.houseVault.fetch(houseVaultPda);
+      expect(vault.authority.toString()).to.equal(house.publicKey.toString());
+    });
+
+    it("Should move the stake into the vault and pay winnings out of it", async () => {
+      const betAmount = 0.1 * LAMPORTS_PER_SOL;
+      const initialVaultBalance = await provider.connection.getBalance(houseVaultPda);
+
+      const gameState = await playRoulette(betAmount, { red: {} });
+
+      const finalVaultBalance = await provider.connection.getBalance(houseVaultPda);
+      expect(finalVaultBalance).to.equal(initialVaultBalance + betAmount - gameState.payout.toNumber());
+      expect(gameState.payout.toNumber()).to.equal(gameState.bets[0].won ? 2 * betAmount : 0);
+
+      const vault = await program.account.houseVault.fetch(houseVaultPda);
+      expect(vault.reserved.toNumber()).to.equal(0);
+    });
+
+    it("Should reject bets the vault cannot cover", async () => {
+      // 1 SOL straight-up bet would owe 36 SOL against a 10 SOL bankroll
+      try {
+        await placeBet(1 * LAMPORTS_PER_SOL, { number: { 0: 7 } });
+        expect.fail("Should have rejected an uncovered bet");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Insufficient house funds for payout");
+      }
+    });
+
+    it("Should only let the authority withdraw", async () => {
+      try {
+        await program.methods
+          .withdrawHouse(new anchor.BN(LAMPORTS_PER_SOL))
+          .accounts({ houseVault: houseVaultPda, authority: player.publicKey })
+          .signers([player])
+          .rpc();
+        expect.fail("Should have rejected a non-authority withdrawal");
+      } catch (error) {
+        expect(error).to.exist;
+      }
+
+      const initialVaultBalance = await provider.connection.getBalance(houseVaultPda);
       await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          { red: {} },
-          0 // bet_value doesn't matter for red/black bets
-        )
-        .accounts({
-          game: gameAccount.publicKey,
-          player: player.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .signers([player, gameAccount])
+        .withdrawHouse(new anchor.BN(LAMPORTS_PER_SOL))
+        .accounts({ houseVault: houseVaultPda, authority: house.publicKey })
         .rpc();
+      const finalVaultBalance = await provider.connection.getBalance(houseVaultPda);
+      expect(finalVaultBalance).to.equal(initialVaultBalance - LAMPORTS_PER_SOL);
+    });
+  });
+
+  describe("Table Config", () => {
+    it("Should initialize with default limits on a single-zero wheel", async () => {
+      const config = await program.account.rouletteConfig.fetch(configPda);
+      expect(config.authority.toString()).to.equal(house.publicKey.toString());
+      expect(config.minBet.toNumber()).to.equal(1_000_000); // 0.001 SOL
+      expect(config.maxBet.toNumber()).to.equal(1_000_000_000); // 1 SOL
+      expect(config.isPaused).to.be.false;
+      expect(config.variant).to.deep.equal({ singleZero: {} });
+    });
+
+    it("Should reject bets while paused", async () => {
+      await updateSettings({ isPaused: true });
+      try {
+        await placeBet(0.01 * LAMPORTS_PER_SOL, { red: {} });
+        expect.fail("Should have rejected a bet on a paused table");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Game is currently paused");
+      } finally {
+        await updateSettings({ isPaused: false });
+      }
+    });
+
+    it("Should enforce per-bet limits", async () => {
+      try {
+        await placeBet(999_999, { red: {} });
+        expect.fail("Should have rejected a bet below the minimum");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Bet amount is too low");
+      }
+
+      await updateSettings({ maxBet: 0.05 * LAMPORTS_PER_SOL });
+      try {
+        await placeBet(0.06 * LAMPORTS_PER_SOL, { red: {} });
+        expect.fail("Should have rejected a bet above the maximum");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Bet amount is too high");
+      } finally {
+        await updateSettings({ maxBet: LAMPORTS_PER_SOL });
+      }
+    });
+
+    it("Should cap the worst-case payout per spin", async () => {
+      await updateSettings({ maxExposure: LAMPORTS_PER_SOL });
+      try {
+        // 0.05 SOL straight up owes 1.8 SOL if it hits
+        await placeBet(0.05 * LAMPORTS_PER_SOL, { number: { 0: 7 } });
+        expect.fail("Should have rejected a bet above the exposure limit");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("exceeds the table exposure limit");
+      } finally {
+        await updateSettings({ maxExposure: 36 * LAMPORTS_PER_SOL });
+      }
+    });
+
+    it("Should reject an inverted bet range", async () => {
+      try {
+        await updateSettings({ minBet: 2 * LAMPORTS_PER_SOL });
+        expect.fail("Should have rejected min_bet > max_bet");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Invalid bet range");
+      }
+    });
+
+    it("Should only let the authority update settings", async () => {
+      try {
+        await program.methods
+          .updateSettings(null, null, null, true, null, null)
+          .accounts({ config: configPda, authority: player.publicKey })
+          .signers([player])
+          .rpc();
+        expect.fail("Should have rejected a non-authority update");
+      } catch (error) {
+        expect(error).to.exist;
+      }
+    });
+  });
+
+  describe("Red/Black Bets", () => {
+    it("Should handle a red bet", async () => {
+      const betAmount = 0.1 * LAMPORTS_PER_SOL; // 0.1 SOL
+
+      const gameState = await playRoulette(betAmount, { red: {} });
 
-      // Fetch the game state
-      const gameState = await program.account.rouletteGame.fetch(gameAccount.publicKey);
-      
       expect(gameState.player.toString()).to.equal(player.publicKey.toString());
-      expect(gameState.betAmount.toNumber()).to.equal(betAmount);
-      expect(gameState.betType).to.deep.equal({ red: {} });
+      expect(gameState.totalWagered.toNumber()).to.equal(betAmount);
+      expect(gameState.bets[0].betType).to.deep.equal({ red: {} });
       expect(gameState.isFinished).to.be.true;
       expect(gameState.spinResult).to.be.at.least(0).and.at.most(36);
 
       // Check if the result matches the win condition
-      const isRedNumber = [1, 3, 5, 7, 9, 12, 14, 16, 18, 19, 21, 23, 25, 27, 30, 32, 34, 36]
-        .includes(gameState.spinResult);
-      
-      expect(gameState.won).to.equal(isRedNumber);
-
-      const finalPlayerBalance = await provider.connection.getBalance(player.publicKey);
-      
-      if (gameState.won) {
-        // Player should have received 2x bet amount (original bet + winnings)
-        // But we need to account for transaction fees
-        expect(finalPlayerBalance).to.be.greaterThan(initialPlayerBalance - betAmount);
-      } else {
-        // Player should have lost the bet amount (plus transaction fees)
-        expect(finalPlayerBalance).to.be.lessThan(initialPlayerBalance - betAmount);
-      }
+      const isRedNumber = RED_NUMBERS.includes(gameState.spinResult);
+
+      expect(gameState.bets[0].won).to.equal(isRedNumber);
     });
 
     it("Should handle a black bet", async () => {
       const betAmount = 0.05 * LAMPORTS_PER_SOL;
-      
-      await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          { black: {} },
-          0
-        )
-        .accounts({
-          game: gameAccount.publicKey,
-          player: player.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .signers([player, gameAccount])
-        .rpc();
 
-      const gameState = await program.account.rouletteGame.fetch(gameAccount.publicKey);
-      
-      expect(gameState.betType).to.deep.equal({ black: {} });
-      
+      const gameState = await playRoulette(betAmount, { black: {} });
+
+      expect(gameState.bets[0].betType).to.deep.equal({ black: {} });
+
       const isBlackNumber = [2, 4, 6, 8, 10, 11, 13, 15, 17, 20, 22, 24, 26, 28, 29, 31, 33, 35]
         .includes(gameState.spinResult);
-      
-      expect(gameState.won).to.equal(isBlackNumber);
+
+      expect(gameState.bets[0].won).to.equal(isBlackNumber);
     });
   });
 
   describe("Odd/Even Bets", () => {
     it("Should handle an odd bet", async () => {
-      const betAmount = 0.2 * LAMPORTS_PER_SOL;
-      
-      await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          { odd: {} },
-          0
-        )
-        .accounts({
-          game: gameAccount.publicKey,
-          player: player.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .signers([player, gameAccount])
-        .rpc();
+      const gameState = await playRoulette(0.2 * LAMPORTS_PER_SOL, { odd: {} });
+
+      expect(gameState.bets[0].betType).to.deep.equal({ odd: {} });
 
-      const gameState = await program.account.rouletteGame.fetch(gameAccount.publicKey);
-      
-      expect(gameState.betType).to.deep.equal({ odd: {} });
-      
       const isOdd = gameState.spinResult !== 0 && gameState.spinResult % 2 === 1;
-      expect(gameState.won).to.equal(isOdd);
+      expect(gameState.bets[0].won).to.equal(isOdd);
     });
 
     it("Should handle an even bet", async () => {
-      const betAmount = 0.15 * LAMPORTS_PER_SOL;
-      
-      await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          { even: {} },
-          0
-        )
-        .accounts({
-          game: gameAccount.publicKey,
-          player: player.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .signers([player, gameAccount])
-        .rpc();
+      const gameState = await playRoulette(0.15 * LAMPORTS_PER_SOL, { even: {} });
+
+      expect(gameState.bets[0].betType).to.deep.equal({ even: {} });
 
-      const gameState = await program.account.rouletteGame.fetch(gameAccount.publicKey);
-      
-      expect(gameState.betType).to.deep.equal({ even: {} });
-      
       const isEven = gameState.spinResult !== 0 && gameState.spinResult % 2 === 0;
-      expect(gameState.won).to.equal(isEven);
+      expect(gameState.bets[0].won).to.equal(isEven);
     });
   });
 
   describe("High/Low Bets", () => {
     it("Should handle a high bet (19-36)", async () => {
-      const betAmount = 0.08 * LAMPORTS_PER_SOL;
-      
-      await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          { high: {} },
-          0
-        )
-        .accounts({
-          game: gameAccount.publicKey,
-          player: player.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .signers([player, gameAccount])
-        .rpc();
+      const gameState = await playRoulette(0.08 * LAMPORTS_PER_SOL, { high: {} });
+
+      expect(gameState.bets[0].betType).to.deep.equal({ high: {} });
 
-      const gameState = await program.account.rouletteGame.fetch(gameAccount.publicKey);
-      
-      expect(gameState.betType).to.deep.equal({ high: {} });
-      
       const isHigh = gameState.spinResult >= 19 && gameState.spinResult <= 36;
-      expect(gameState.won).to.equal(isHigh);
+      expect(gameState.bets[0].won).to.equal(isHigh);
     });
 
     it("Should handle a low bet (1-18)", async () => {
-      const betAmount = 0.12 * LAMPORTS_PER_SOL;
-      
-      await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          { low: {} },
-          0
-        )
-        .accounts({
-          game: gameAccount.publicKey,
-          player: player.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .signers([player, gameAccount])
-        .rpc();
+      const gameState = await playRoulette(0.12 * LAMPORTS_PER_SOL, { low: {} });
+
+      expect(gameState.bets[0].betType).to.deep.equal({ low: {} });
 
-      const gameState = await program.account.rouletteGame.fetch(gameAccount.publicKey);
-      
-      expect(gameState.betType).to.deep.equal({ low: {} });
-      
       const isLow = gameState.spinResult >= 1 && gameState.spinResult <= 18;
-      expect(gameState.won).to.equal(isLow);
+      expect(gameState.bets[0].won).to.equal(isLow);
     });
   });
 
   describe("Number Bets", () => {
     it("Should handle a specific number bet", async () => {
-      const betAmount = 0.01 * LAMPORTS_PER_SOL;
       const betNumber = 17;
-      
-      const initialPlayerBalance = await provider.connection.getBalance(player.publicKey);
-      
-      await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          { number: {} },
-          betNumber
-        )
-        .accounts({
-          game: gameAccount.publicKey,
-          player: player.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .signers([player, gameAccount])
-        .rpc();
 
-      const gameState = await program.account.rouletteGame.fetch(gameAccount.publicKey);
-      
-      expect(gameState.betType).to.deep.equal({ number: {} });
-      expect(gameState.betValue).to.equal(betNumber);
-      
+      const gameState = await playRoulette(0.01 * LAMPORTS_PER_SOL, { number: { 0: betNumber } });
+
+      expect(gameState.bets[0].betType).to.deep.equal({ number: { 0: betNumber } });
+
       const wonNumber = gameState.spinResult === betNumber;
-      expect(gameState.won).to.equal(wonNumber);
-
-      const finalPlayerBalance = await provider.connection.getBalance(player.publicKey);
-      
-      if (gameState.won) {
-        // Player should have received 36x bet amount for a winning number bet
-        // But we need to account for transaction fees
-        expect(finalPlayerBalance).to.be.greaterThan(initialPlayerBalance + (betAmount * 35)); // 35x profit
-      }
+      expect(gameState.bets[0].won).to.equal(wonNumber);
     });
 
     it("Should reject invalid number bets", async () => {
-      const betAmount = 0.01 * LAMPORTS_PER_SOL;
       const invalidBetNumber = 37; // Should be <= 36
-      
+
       try {
-        await program.methods
-          .playRoulette(
-            new anchor.BN(betAmount),
-            { number: {} },
-            invalidBetNumber
-          )
-          .accounts({
-            game: gameAccount.publicKey,
-            player: player.publicKey,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
-          .signers([player, gameAccount])
-          .rpc();
-        
+        await placeBet(0.01 * LAMPORTS_PER_SOL, { number: { 0: invalidBetNumber } });
         expect.fail("Should have thrown an error for invalid bet value");
       } catch (error) {
         expect(error.error.errorMessage).to.include("Invalid bet value");
@@ -270,58 +509,322 @@ describe("roulette", () => {
     });
   });
 
+  describe("Inside and Outside Bets", () => {
+    const cases: { name: string; betType: any; covered: number[]; multiplier: number }[] = [
+      { name: "split", betType: { split: { 0: [14, 17] } }, covered: [14, 17], multiplier: 18 },
+      { name: "street", betType: { street: { 0: 7 } }, covered: [7, 8, 9], multiplier: 12 },
+      { name: "corner", betType: { corner: { 0: 20 } }, covered: [20, 21, 23, 24], multiplier: 9 },
+      { name: "six line", betType: { sixLine: { 0: 31 } }, covered: [31, 32, 33, 34, 35, 36], multiplier: 6 },
+      {
+        name: "dozen",
+        betType: { dozen: { 0: 2 } },
+        covered: Array.from({ length: 12 }, (_, i) => 13 + i),
+        multiplier: 3,
+      },
+      {
+        name: "column",
+        betType: { column: { 0: 3 } },
+        covered: Array.from({ length: 12 }, (_, i) => 3 + 3 * i),
+        multiplier: 3,
+      },
+      { name: "basket", betType: { basket: {} }, covered: [0, 1, 2, 3], multiplier: 9 },
+      { name: "zero split", betType: { zeroSplit: { 0: [0, 2] } }, covered: [0, 2], multiplier: 18 },
+    ];
+
+    for (const { name, betType, covered, multiplier } of cases) {
+      it(`Should settle a ${name} bet at the standard odds`, async () => {
+        const betAmount = 0.01 * LAMPORTS_PER_SOL;
+
+        const gameState = await playRoulette(betAmount, betType);
+
+        expect(gameState.bets[0].won).to.equal(covered.includes(gameState.spinResult));
+        expect(gameState.payout.toNumber()).to.equal(gameState.bets[0].won ? multiplier * betAmount : 0);
+      });
+    }
+
+    const invalid: { name: string; betType: any; message: string }[] = [
+      { name: "non-adjacent split", betType: { split: { 0: [3, 4] } }, message: "Split must cover two adjacent numbers" },
+      { name: "misaligned street", betType: { street: { 0: 2 } }, message: "Street must start on the first number of a row" },
+      { name: "corner off the right edge", betType: { corner: { 0: 3 } }, message: "Corner must start on the top-left number of a 2x2 block" },
+      { name: "six line past the last row", betType: { sixLine: { 0: 34 } }, message: "Six line must start on the first number of a row" },
+      { name: "fourth dozen", betType: { dozen: { 0: 4 } }, message: "Dozen must be 1, 2 or 3" },
+      { name: "zero column", betType: { column: { 0: 0 } }, message: "Column must be 1, 2 or 3" },
+      { name: "zero split without zero", betType: { zeroSplit: { 0: [1, 2] } }, message: "Zero split must pair a zero pocket with an adjacent number" },
+      { name: "five-number bet on a single-zero wheel", betType: { fiveNumber: {} }, message: "Bet is not available on this wheel variant" },
+    ];
+
+    for (const { name, betType, message } of invalid) {
+      it(`Should reject a ${name}`, async () => {
+        try {
+          await placeBet(0.01 * LAMPORTS_PER_SOL, betType);
+          expect.fail(`Should have rejected a ${name}`);
+        } catch (error) {
+          expect(error.error.errorMessage).to.include(message);
+        }
+      });
+    }
+  });
+
+  describe("American Variant", () => {
+    const DOUBLE_ZERO = 37;
+
+    beforeAll(async () => {
+      await updateSettings({ variant: { doubleZero: {} } });
+    });
+
+    afterAll(async () => {
+      await updateSettings({ variant: { singleZero: {} } });
+    });
+
+    it("Should draw from 38 pockets and record the variant", async () => {
+      const gameState = await playRoulette(0.01 * LAMPORTS_PER_SOL, { red: {} });
+
+      expect(gameState.variant).to.deep.equal({ doubleZero: {} });
+      expect(gameState.spinResult).to.be.at.least(0).and.at.most(DOUBLE_ZERO);
+      expect(gameState.spinResult).to.equal(
+        deriveSpinResult(gameState.variant, gameState.houseSeed, gameState.playerSeed, gameState.slotHash)
+      );
+      expect(gameState.bets[0].won).to.equal(RED_NUMBERS.includes(gameState.spinResult));
+    });
+
+    it("Should pay the five-number bet at 6:1", async () => {
+      const betAmount = 0.01 * LAMPORTS_PER_SOL;
+
+      const gameState = await playRoulette(betAmount, { fiveNumber: {} });
+
+      const won = [0, DOUBLE_ZERO, 1, 2, 3].includes(gameState.spinResult);
+      expect(gameState.bets[0].won).to.equal(won);
+      expect(gameState.payout.toNumber()).to.equal(won ? 7 * betAmount : 0);
+    });
+
+    it("Should accept straight-up and split bets on 00", async () => {
+      const houseSeed = await placeBets([
+        { betType: { number: { 0: DOUBLE_ZERO } }, amount: 0.01 * LAMPORTS_PER_SOL },
+        { betType: { zeroSplit: { 0: [DOUBLE_ZERO, 3] } }, amount: 0.01 * LAMPORTS_PER_SOL },
+        { betType: { zeroSplit: { 0: [0, DOUBLE_ZERO] } }, amount: 0.01 * LAMPORTS_PER_SOL },
+      ]);
+      const gameState = await settleBet(houseSeed);
+
+      expect(gameState.bets[0].won).to.equal(gameState.spinResult === DOUBLE_ZERO);
+      expect(gameState.bets[1].won).to.equal([DOUBLE_ZERO, 3].includes(gameState.spinResult));
+      expect(gameState.bets[2].won).to.equal([0, DOUBLE_ZERO].includes(gameState.spinResult));
+    });
+
+    it("Should reject the single-zero basket and a 0-3 split", async () => {
+      for (const betType of [{ basket: {} }, { zeroSplit: { 0: [0, 3] } }]) {
+        try {
+          await placeBet(0.01 * LAMPORTS_PER_SOL, betType);
+          expect.fail("Should have rejected a single-zero layout on a double-zero wheel");
+        } catch (error) {
+          expect(error.error.errorMessage).to.match(/not available on this wheel|Zero split/);
+        }
+      }
+    });
+  });
+
+  describe("Zero Rules", () => {
+    afterEach(async () => {
+      await updateSettings({ evenMoneyRule: { standard: {} } });
+    });
+
+    it("Should default to the standard rule", async () => {
+      const config = await program.account.rouletteConfig.fetch(configPda);
+      expect(config.evenMoneyRule).to.deep.equal({ standard: {} });
+    });
+
+    it("Should refund half an even-money stake on zero under La Partage", async () => {
+      await updateSettings({ evenMoneyRule: { laPartage: {} } });
+      const betAmount = 0.02 * LAMPORTS_PER_SOL;
+
+      const gameState = await playRoulette(betAmount, { red: {} });
+
+      expect(gameState.evenMoneyRule).to.deep.equal({ laPartage: {} });
+      if (gameState.spinResult === 0) {
+        expect(gameState.bets[0].won).to.be.false;
+        expect(gameState.payout.toNumber()).to.equal(betAmount / 2);
+      } else {
+        expect(gameState.payout.toNumber()).to.equal(gameState.bets[0].won ? 2 * betAmount : 0);
+      }
+    });
+
+    it("Should hold an even-money stake for the next spin under En Prison", async () => {
+      await updateSettings({ evenMoneyRule: { enPrison: {} } });
+      const betAmount = 0.02 * LAMPORTS_PER_SOL;
+
+      const first = await playRoulette(betAmount, { black: {} });
+      expect(first.bets[0].imprisoned).to.equal(first.spinResult === 0);
+      expect(first.reserved.toNumber()).to.equal(first.spinResult === 0 ? betAmount : 0);
+
+      // The same game account is reused; an imprisoned bet rides along with the new one
+      const second = await playRoulette(betAmount, { red: {} });
+      const carried = first.spinResult === 0 ? 1 : 0;
+      expect(second.bets.length).to.equal(1 + carried);
+      if (carried) {
+        const released = second.bets[0];
+        expect(released.betType).to.deep.equal({ black: {} });
+        expect(released.imprisoned).to.be.false;
+        expect(released.payout.toNumber()).to.equal(released.won ? betAmount : 0);
+      }
+    });
+
+    it("Should not reuse a game account before its spin settles", async () => {
+      await placeBet(0.01 * LAMPORTS_PER_SOL, { red: {} });
+
+      try {
+        await placeBet(0.01 * LAMPORTS_PER_SOL, { black: {} });
+        expect.fail("Should have rejected a second bet on an unsettled game");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Previous spin on this game account is not settled");
+      }
+    });
+
+    it("Should not close a game while a bet is imprisoned", async () => {
+      await updateSettings({ evenMoneyRule: { enPrison: {} } });
+
+      const gameState = await playRoulette(0.01 * LAMPORTS_PER_SOL, { red: {} });
+      if (gameState.spinResult !== 0) return; // nothing imprisoned this time
+
+      try {
+        await closeGame();
+        expect.fail("Should have rejected closing with an imprisoned bet");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Imprisoned bets must resolve");
+      }
+    });
+  });
+
+  describe("Events", () => {
+    it("Should emit BetPlaced and BetSettled", async () => {
+      const events: Record<string, any> = {};
+      const listeners = ["betPlaced", "betSettled"].map((name) =>
+        program.addEventListener(name as any, (event) => {
+          events[name] = event;
+        })
+      );
+
+      const betAmount = 0.01 * LAMPORTS_PER_SOL;
+      try {
+        const gameState = await playRoulette(betAmount, { odd: {} });
+        await new Promise((resolve) => setTimeout(resolve, 1000));
+
+        expect(events.betPlaced.player.toString()).to.equal(player.publicKey.toString());
+        expect(events.betPlaced.totalWagered.toNumber()).to.equal(betAmount);
+        expect(events.betSettled.game.toString()).to.equal(gamePda(player.publicKey).toString());
+        expect(events.betSettled.spinResult).to.equal(gameState.spinResult);
+        expect(events.betSettled.payout.toNumber()).to.equal(gameState.payout.toNumber());
+        expect(events.betSettled.timestamp.toNumber()).to.be.greaterThan(0);
+      } finally {
+        for (const listener of listeners) {
+          await program.removeEventListener(listener);
+        }
+      }
+    });
+  });
+
+  describe("Multi-Bet Spins", () => {
+    it("Should settle every bet against one spin and pay the total once", async () => {
+      const bets = [
+        { betType: { red: {} }, amount: 0.02 * LAMPORTS_PER_SOL, covered: RED_NUMBERS, multiplier: 2 },
+        { betType: { dozen: { 0: 1 } }, amount: 0.01 * LAMPORTS_PER_SOL, covered: [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12], multiplier: 3 },
+        { betType: { number: { 0: 0 } }, amount: 0.005 * LAMPORTS_PER_SOL, covered: [0], multiplier: 36 },
+      ];
+      const totalWagered = bets.reduce((sum, bet) => sum + bet.amount, 0);
+      const initialVaultBalance = await provider.connection.getBalance(houseVaultPda);
+
+      const houseSeed = await placeBets(bets);
+      const gameState = await settleBet(houseSeed);
+
+      expect(gameState.bets.length).to.equal(bets.length);
+      expect(gameState.totalWagered.toNumber()).to.equal(totalWagered);
+
+      let expectedPayout = 0;
+      bets.forEach((bet, i) => {
+        const won = bet.covered.includes(gameState.spinResult);
+        const payout = won ? bet.amount * bet.multiplier : 0;
+        expect(gameState.bets[i].won).to.equal(won);
+        expect(gameState.bets[i].payout.toNumber()).to.equal(payout);
+        expectedPayout += payout;
+      });
+      expect(gameState.payout.toNumber()).to.equal(expectedPayout);
+
+      const finalVaultBalance = await provider.connection.getBalance(houseVaultPda);
+      expect(finalVaultBalance).to.equal(initialVaultBalance + totalWagered - expectedPayout);
+    });
+
+    it("Should reject an empty bet list", async () => {
+      try {
+        await placeBets([]);
+        expect.fail("Should have rejected an empty bet list");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Invalid number of bets");
+      }
+    });
+
+    it("Should reject more bets than the table allows", async () => {
+      const bets = Array.from({ length: 17 }, (_, i) => ({
+        betType: { number: { 0: i } },
+        amount: 1_000_000,
+      }));
+
+      try {
+        await placeBets(bets);
+        expect.fail("Should have rejected too many bets");
+      } catch (error) {
+        expect(error).to.exist;
+      }
+    });
+  });
+
   describe("Error Cases", () => {
     it("Should reject zero bet amount", async () => {
+      try {
+        await placeBet(0, { red: {} });
+        expect.fail("Should have thrown an error for zero bet amount");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Invalid bet amount");
+      }
+    });
+
+    it("Should require the house to co-sign the bet", async () => {
       try {
         await program.methods
-          .playRoulette(
-            new anchor.BN(0),
+          .placeBet(
+            new anchor.BN(0.01 * LAMPORTS_PER_SOL),
             { red: {} },
-            0
+            Array.from(sha256([crypto.randomBytes(32)])),
+            Array.from(crypto.randomBytes(32))
           )
           .accounts({
-            game: gameAccount.publicKey,
+            game: gamePda(player.publicKey),
+            config: configPda,
+            houseVault: houseVaultPda,
             player: player.publicKey,
+            house: Keypair.generate().publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
           })
-          .signers([player, gameAccount])
+          .signers([player])
           .rpc();
-        
-        expect.fail("Should have thrown an error for zero bet amount");
+
+        expect.fail("Should have failed without the house signature");
       } catch (error) {
-        expect(error.error.errorMessage).to.include("Invalid bet amount");
+        expect(error).to.exist;
       }
     });
 
     it("Should fail if player has insufficient funds", async () => {
       // Create a player with very little SOL
       const poorPlayer = Keypair.generate();
-      const poorGameAccount = Keypair.generate();
-      
+
       // Give just enough SOL for transaction fees but not for the bet
       const signature = await provider.connection.requestAirdrop(
         poorPlayer.publicKey,
         0.001 * LAMPORTS_PER_SOL
       );
       await provider.connection.confirmTransaction(signature);
+      player = poorPlayer;
 
-      const largeBetAmount = 1 * LAMPORTS_PER_SOL; // 1 SOL bet
-      
       try {
-        await program.methods
-          .playRoulette(
-            new anchor.BN(largeBetAmount),
-            { red: {} },
-            0
-          )
-          .accounts({
-            game: poorGameAccount.publicKey,
-            player: poorPlayer.publicKey,
-            systemProgram: anchor.web3.SystemProgram.programId,
-          })
-          .signers([poorPlayer, poorGameAccount])
-          .rpc();
-        
+        await placeBet(1 * LAMPORTS_PER_SOL, { red: {} });
         expect.fail("Should have failed due to insufficient funds");
       } catch (error) {
         // This should fail at the system program level due to insufficient funds
@@ -334,81 +837,369 @@ describe("roulette", () => {
     it("Should properly set all game state fields", async () => {
       const betAmount = 0.05 * LAMPORTS_PER_SOL;
       const betNumber = 25;
-      
-      await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          { number: {} },
-          betNumber
-        )
-        .accounts({
-          game: gameAccount.publicKey,
-          player: player.publicKey,
-          systemProgram: anchor.web3.SystemProgram.programId,
-        })
-        .signers([player, gameAccount])
-        .rpc();
 
-      const gameState = await program.account.rouletteGame.fetch(gameAccount.publicKey);
-      
+      const gameState = await playRoulette(betAmount, { number: { 0: betNumber } });
+
       // Verify all fields are set correctly
       expect(gameState.player.toString()).to.equal(player.publicKey.toString());
-      expect(gameState.betAmount.toNumber()).to.equal(betAmount);
-      expect(gameState.betType).to.deep.equal({ number: {} });
-      expect(gameState.betValue).to.equal(betNumber);
+      expect(gameState.house.toString()).to.equal(house.publicKey.toString());
+      expect(gameState.totalWagered.toNumber()).to.equal(betAmount);
+      expect(gameState.bets[0].betType).to.deep.equal({ number: { 0: betNumber } });
       expect(gameState.spinResult).to.be.at.least(0).and.at.most(36);
       expect(gameState.isFinished).to.be.true;
-      
+
       // Verify win condition
       const shouldWin = gameState.spinResult === betNumber;
-      expect(gameState.won).to.equal(shouldWin);
+      expect(gameState.bets[0].won).to.equal(shouldWin);
     });
   });
 
-  describe("Multiple Games", () => {
-    it("Should allow multiple games with different accounts", async () => {
-      const betAmount = 0.02 * LAMPORTS_PER_SOL;
-      
-      // First game
-      const gameAccount1 = Keypair.generate();
+  describe("Game Account Lifecycle", () => {
+    it("Should reuse one account per player and keep a spin history", async () => {
+      const betAmount = 0.01 * LAMPORTS_PER_SOL;
+
+      const first = await playRoulette(betAmount, { red: {} });
+      const second = await playRoulette(betAmount, { black: {} });
+
+      expect(second.totalSpins.toNumber()).to.equal(2);
+      expect(second.bets[0].betType).to.deep.equal({ black: {} });
+      expect(second.history[0].spinResult).to.equal(first.spinResult);
+      expect(second.history[0].payout.toNumber()).to.equal(first.payout.toNumber());
+      expect(second.history[1].spinResult).to.equal(second.spinResult);
+      expect(second.history[1].totalWagered.toNumber()).to.equal(betAmount);
+      expect(second.history[1].betSlot.toNumber()).to.equal(second.betSlot.toNumber());
+    });
+
+    it("Should close a settled game and return the rent", async () => {
+      await playRoulette(0.01 * LAMPORTS_PER_SOL, { odd: {} });
+      const game = gamePda(player.publicKey);
+      const rent = await provider.connection.getBalance(game);
+      const initialBalance = await provider.connection.getBalance(player.publicKey);
+
+      await closeGame();
+
+      expect(await provider.connection.getAccountInfo(game)).to.be.null;
+      // The player pays the tx fee out of the returned rent
+      expect(await provider.connection.getBalance(player.publicKey)).to.be.greaterThan(
+        initialBalance + rent - 0.0001 * LAMPORTS_PER_SOL
+      );
+
+      // The account is recreated on the next spin
+      const gameState = await playRoulette(0.01 * LAMPORTS_PER_SOL, { even: {} });
+      expect(gameState.totalSpins.toNumber()).to.equal(1);
+    });
+
+    it("Should not close a game before its spin settles", async () => {
+      await placeBet(0.01 * LAMPORTS_PER_SOL, { red: {} });
+
+      try {
+        await closeGame();
+        expect.fail("Should have rejected closing an unsettled game");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Previous spin on this game account is not settled");
+      }
+    });
+  });
+
+  describe("SPL Token Bets", () => {
+    const anchorProvider = provider as anchor.AnchorProvider;
+    let mint: PublicKey;
+    let tokenHousePda: PublicKey;
+    let tokenVaultPda: PublicKey;
+    let playerTokens: PublicKey;
+
+    beforeAll(async () => {
+      mint = await createMint(anchorProvider, house);
+      [tokenHousePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_house"), mint.toBuffer()],
+        program.programId
+      );
+      [tokenVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_vault"), mint.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeTokenHouse(new anchor.BN(1_000), new anchor.BN(1_000_000), new anchor.BN(50_000_000))
+        .accounts({
+          tokenHouse: tokenHousePda,
+          tokenVault: tokenVaultPda,
+          mint,
+          houseVault: houseVaultPda,
+          authority: house.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      const houseTokens = await createTokenAccount(anchorProvider, house, mint, house.publicKey);
+      await mintTo(anchorProvider, house, mint, houseTokens, 100_000_000);
       await program.methods
-        .playRoulette(
+        .fundTokenHouse(new anchor.BN(100_000_000))
+        .accounts({
+          tokenHouse: tokenHousePda,
+          tokenVault: tokenVaultPda,
+          mint,
+          funderTokenAccount: houseTokens,
+          funder: house.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    });
+
+    beforeEach(async () => {
+      playerTokens = await createTokenAccount(anchorProvider, house, mint, player.publicKey);
+      await mintTo(anchorProvider, house, mint, playerTokens, 10_000_000);
+    });
+
+    const tokenAccounts = () => ({
+      tokenHouse: tokenHousePda,
+      houseTokenAccount: tokenVaultPda,
+      playerTokenAccount: playerTokens,
+      mint,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    });
+
+    it("Should stake and settle a bet in the whitelisted mint", async () => {
+      const betAmount = 500_000;
+      const houseSeed = crypto.randomBytes(32);
+
+      await program.methods
+        .placeBet(
           new anchor.BN(betAmount),
-          "red",
-          0
+          { red: {} },
+          Array.from(sha256([houseSeed])),
+          Array.from(crypto.randomBytes(32))
         )
         .accounts({
-          game: gameAccount1.publicKey,
+          game: gamePda(player.publicKey),
+          config: configPda,
+          houseVault: houseVaultPda,
           player: player.publicKey,
+          house: house.publicKey,
+          token: tokenAccounts(),
           systemProgram: anchor.web3.SystemProgram.programId,
         })
-        .signers([player, gameAccount1])
+        .signers([player, house])
         .rpc();
 
-      // Second game
-      const gameAccount2 = Keypair.generate();
+      const placed = await program.account.rouletteGame.fetch(gamePda(player.publicKey));
+      expect(placed.mint.toString()).to.equal(mint.toString());
+      expect(await tokenBalance(anchorProvider, playerTokens)).to.equal(10_000_000 - betAmount);
+      expect((await program.account.tokenHouse.fetch(tokenHousePda)).reserved.toNumber()).to.equal(2 * betAmount);
+
+      await waitForNextSlot(placed.betSlot.toNumber());
       await program.methods
-        .playRoulette(
-          new anchor.BN(betAmount),
-          "black",
-          0
-        )
+        .settleBet(Array.from(houseSeed))
+        .accounts({
+          game: gamePda(player.publicKey),
+          houseVault: houseVaultPda,
+          player: player.publicKey,
+          house: house.publicKey,
+          recentSlothashes: SYSVAR_SLOT_HASHES_PUBKEY,
+          token: tokenAccounts(),
+        })
+        .signers([house])
+        .rpc();
+
+      const settled = await program.account.rouletteGame.fetch(gamePda(player.publicKey));
+      const tokenHouse = await program.account.tokenHouse.fetch(tokenHousePda);
+      expect(tokenHouse.reserved.toNumber()).to.equal(0);
+      expect(tokenHouse.totalPaidOut.toNumber()).to.be.at.least(settled.payout.toNumber());
+      expect(await tokenBalance(anchorProvider, playerTokens)).to.equal(
+        10_000_000 - betAmount + settled.payout.toNumber()
+      );
+    });
+
+    it("Should apply the token house limits instead of the SOL limits", async () => {
+      try {
+        await program.methods
+          .placeBet(
+            new anchor.BN(2_000_000),
+            { red: {} },
+            Array.from(sha256([crypto.randomBytes(32)])),
+            Array.from(crypto.randomBytes(32))
+          )
+          .accounts({
+            game: gamePda(player.publicKey),
+            config: configPda,
+            houseVault: houseVaultPda,
+            player: player.publicKey,
+            house: house.publicKey,
+            token: tokenAccounts(),
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([player, house])
+          .rpc();
+        expect.fail("Should have rejected a bet above the token max");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Bet amount is too high");
+      }
+    });
+
+    it("Should apply the player's limits in the token's units and refuse unlisted currencies", async () => {
+      const zero = new anchor.BN(0);
+      await program.methods
+        .setLimits({
+          currencies: [{ mint, dailyLossLimit: zero, weeklyLossLimit: zero, maxBet: new anchor.BN(100_000) }],
+          sessionCooldown: zero,
+          excludedUntil: zero,
+        })
+        .accounts({
+          playerLimits: PublicKey.findProgramAddressSync(
+            [Buffer.from("limits"), player.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          player: player.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([player])
+        .rpc();
+
+      try {
+        await program.methods
+          .placeBet(
+            new anchor.BN(500_000),
+            { red: {} },
+            Array.from(sha256([crypto.randomBytes(32)])),
+            Array.from(crypto.randomBytes(32))
+          )
+          .accounts({
+            game: gamePda(player.publicKey),
+            config: configPda,
+            houseVault: houseVaultPda,
+            player: player.publicKey,
+            house: house.publicKey,
+            token: tokenAccounts(),
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .signers([player, house])
+          .rpc();
+        expect.fail("Should have applied the player's token max bet");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Bet is above the player's max bet");
+      }
+
+      // Only the token is listed, so SOL bets are refused
+      try {
+        await placeBet(0.01 * LAMPORTS_PER_SOL, { red: {} });
+        expect.fail("Should have refused a currency the limits do not list");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Player's limits do not cover this currency");
+      }
+    });
+
+    it("Should refuse to whitelist a mint that charges a transfer fee", async () => {
+      const feeMint = await createTransferFeeMint(anchorProvider, house, 100, 1_000_000);
+      const [feeHousePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_house"), feeMint.toBuffer()],
+        program.programId
+      );
+      const [feeVaultPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("token_vault"), feeMint.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .initializeTokenHouse(new anchor.BN(1_000), new anchor.BN(1_000_000), new anchor.BN(50_000_000))
+          .accounts({
+            tokenHouse: feeHousePda,
+            tokenVault: feeVaultPda,
+            mint: feeMint,
+            houseVault: houseVaultPda,
+            authority: house.publicKey,
+            tokenProgram: TOKEN_2022_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .rpc();
+        expect.fail("Should have rejected a transfer-fee mint");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Mints with a transfer fee are not supported");
+      }
+    });
+  });
+
+  describe("Player Limits", () => {
+    const limitsPda = (owner: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("limits"), owner.toBuffer()], program.programId)[0];
+
+    async function setLimits(limits: {
+      mint?: PublicKey;
+      dailyLossLimit?: number;
+      weeklyLossLimit?: number;
+      maxBet?: number;
+      sessionCooldown?: number;
+      excludedUntil?: number;
+    }) {
+      const bn = (n?: number) => new anchor.BN(n ?? 0);
+      await program.methods
+        .setLimits({
+          currencies: [
+            {
+              mint: limits.mint ?? PublicKey.default,
+              dailyLossLimit: bn(limits.dailyLossLimit),
+              weeklyLossLimit: bn(limits.weeklyLossLimit),
+              maxBet: bn(limits.maxBet),
+            },
+          ],
+          sessionCooldown: bn(limits.sessionCooldown),
+          excludedUntil: bn(limits.excludedUntil),
+        })
         .accounts({
-          game: gameAccount2.publicKey,
+          playerLimits: limitsPda(player.publicKey),
           player: player.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
-        .signers([player, gameAccount2])
+        .signers([player])
         .rpc();
+      return program.account.playerLimits.fetch(limitsPda(player.publicKey));
+    }
+
+    it("Should count stakes against the daily loss limit", async () => {
+      await setLimits({ dailyLossLimit: 0.05 * LAMPORTS_PER_SOL });
+
+      try {
+        await placeBet(0.06 * LAMPORTS_PER_SOL, { red: {} });
+        expect.fail("Should have rejected a bet above the loss limit");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Bet would exceed the player's loss limit");
+      }
 
-      // Verify both games exist and have correct data
-      const gameState1 = await program.account.rouletteGame.fetch(gameAccount1.publicKey);
-      const gameState2 = await program.account.rouletteGame.fetch(gameAccount2.publicKey);
-      
-      expect(gameState1.betType).to.deep.equal({ red: {} });
-      expect(gameState2.betType).to.deep.equal({ black: {} });
-      expect(gameState1.isFinished).to.be.true;
-      expect(gameState2.isFinished).to.be.true;
+      await placeBet(0.05 * LAMPORTS_PER_SOL, { red: {} });
+      const limits = await program.account.playerLimits.fetch(limitsPda(player.publicKey));
+      expect(limits.limits.losses[0].dayLoss.toNumber()).to.equal(0.05 * LAMPORTS_PER_SOL);
+      expect(limits.limits.losses[0].weekLoss.toNumber()).to.equal(0.05 * LAMPORTS_PER_SOL);
+    });
+
+    it("Should tighten the max bet at once but delay loosening it", async () => {
+      let limits = await setLimits({ maxBet: 0.01 * LAMPORTS_PER_SOL });
+      expect(limits.limits.settings.currencies[0].maxBet.toNumber()).to.equal(0.01 * LAMPORTS_PER_SOL);
+      expect(limits.limits.pendingAt.toNumber()).to.equal(0);
+
+      // Removing the limit only queues the change
+      limits = await setLimits({});
+      expect(limits.limits.settings.currencies[0].maxBet.toNumber()).to.equal(0.01 * LAMPORTS_PER_SOL);
+      expect(limits.limits.pending.currencies[0].maxBet.toNumber()).to.equal(0);
+      expect(limits.limits.pendingAt.toNumber()).to.be.greaterThan(Date.now() / 1000 + 23 * 60 * 60);
+
+      try {
+        await placeBet(0.02 * LAMPORTS_PER_SOL, { red: {} });
+        expect.fail("Should have kept the max bet until the delay passes");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Bet is above the player's max bet");
+      }
+    });
+
+    it("Should refuse every bet while the player is self-excluded", async () => {
+      await setLimits({ excludedUntil: Math.floor(Date.now() / 1000) + 3600 });
+
+      try {
+        await placeBets([{ betType: { red: {} }, amount: 0.01 * LAMPORTS_PER_SOL }]);
+        expect.fail("Should have rejected a self-excluded player");
+      } catch (error) {
+        expect(error.error.errorMessage).to.include("Player is self-excluded");
+      }
     });
   });
-});
\ No newline at end of file
+});
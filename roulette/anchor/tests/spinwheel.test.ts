@@ -2,6 +2,22 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program, BN, web3 } from "@coral-xyz/anchor";
 import { Spinwheel } from "../target/types/spinwheel";
 import { expect } from "chai";
+import * as crypto from "crypto";
+import {
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  createMint,
+  createTokenAccount,
+  createTransferFeeMint,
+  mintTo,
+  tokenBalance,
+} from "./token-utils";
+
+const sha256 = (parts: Uint8Array[]) => {
+  const h = crypto.createHash("sha256");
+  parts.forEach((p) => h.update(p));
+  return h.digest();
+};
 
 describe("spinwheel", () => {
   // Configure the client to use the local cluster
@@ -11,9 +27,12 @@ describe("spinwheel", () => {
   const program = anchor.workspace.Spinwheel as Program<Spinwheel>;
   const authority = provider.wallet as anchor.Wallet;
 
+  const TABLE_ID = 0;
+
   // PDAs
   let gameStatePda: web3.PublicKey;
   let houseVaultPda: web3.PublicKey;
+  let wheelConfigPda: web3.PublicKey;
   let gameStateBump: number;
 
   // Helper to confirm airdrop
@@ -22,26 +41,89 @@ describe("spinwheel", () => {
     await provider.connection.confirmTransaction(tx);
   }
 
-  beforeAll(async () => {
-    // Find PDAs
-    [gameStatePda, gameStateBump] = web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("game_state")],
+  function pendingSpinPda(player: web3.PublicKey) {
+    return web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("spin"), gameStatePda.toBuffer(), player.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // Phase 1: the authority co-signs as the house with a commitment to its seed
+  async function requestSpin(
+    player: web3.Keypair,
+    betAmount: BN,
+    prediction: number,
+    houseSeed: Uint8Array = crypto.randomBytes(32)
+  ) {
+    await program.methods
+      .spin(betAmount, prediction, Array.from(sha256([houseSeed])), Array.from(crypto.randomBytes(32)))
+      .accounts({
+        gameState: gameStatePda,
+        houseVault: houseVaultPda,
+        wheelConfig: wheelConfigPda,
+        pendingSpin: pendingSpinPda(player.publicKey),
+        player: player.publicKey,
+        house: authority.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([player, authority.payer])
+      .rpc();
+    return houseSeed;
+  }
+
+  // Phase 2: the house reveals its seed once the spin slot hash exists
+  async function fulfilSpin(player: web3.PublicKey, houseSeed: Uint8Array) {
+    await new Promise((resolve) => setTimeout(resolve, 500));
+    return program.methods
+      .fulfilSpin(Array.from(houseSeed))
+      .accounts({
+        gameState: gameStatePda,
+        houseVault: houseVaultPda,
+        wheelConfig: wheelConfigPda,
+        pendingSpin: pendingSpinPda(player),
+        player,
+        house: authority.publicKey,
+        recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
+      })
+      .rpc();
+  }
+
+  // Every table derives its own state, vault and wheel from its creator and id
+  function tablePdas(tableId: number, creator: web3.PublicKey = authority.publicKey) {
+    const [gameState, bump] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("game_state"), creator.toBuffer(), new BN(tableId).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [houseVault] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("house_vault"), gameState.toBuffer()],
       program.programId
     );
-    [houseVaultPda] = web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("house_vault")],
+    const [wheelConfig] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("wheel_config"), gameState.toBuffer()],
       program.programId
     );
+    return { gameState, bump, houseVault, wheelConfig };
+  }
+
+  beforeAll(async () => {
+    // Find PDAs
+    ({
+      gameState: gameStatePda,
+      bump: gameStateBump,
+      houseVault: houseVaultPda,
+      wheelConfig: wheelConfigPda,
+    } = tablePdas(TABLE_ID));
   });
 
   it("Initializes the game state", async () => {
     const houseEdge = 5;
 
     const tx = await program.methods
-      .initialize(houseEdge)
+      .initialize(new BN(TABLE_ID), houseEdge)
       .accounts({
         gameState: gameStatePda,
         houseVault: houseVaultPda,
+        wheelConfig: wheelConfigPda,
         authority: authority.publicKey,
         systemProgram: web3.SystemProgram.programId,
       })
@@ -60,6 +142,12 @@ describe("spinwheel", () => {
     expect(gameStateAccount.minBet.toNumber()).to.equal(1_000_000); // 0.001 SOL
     expect(gameStateAccount.maxBet.toNumber()).to.equal(1_000_000_000); // 1 SOL
     expect(gameStateAccount.bump).to.equal(gameStateBump); // Verify bump
+
+    // Default wheel: 8 equal segments paying 8x
+    const wheelConfig = await program.account.wheelConfig.fetch(wheelConfigPda);
+    expect(wheelConfig.segments.length).to.equal(8);
+    expect(wheelConfig.totalWeight).to.equal(8);
+    expect(wheelConfig.maxMultiplier).to.equal(8);
   });
 
   it("Funds the house vault", async () => {
@@ -68,6 +156,7 @@ describe("spinwheel", () => {
     const tx = await program.methods
       .fundHouseVault(fundAmount)
       .accounts({
+        gameState: gameStatePda,
         houseVault: houseVaultPda,
         funder: authority.publicKey,
         systemProgram: web3.SystemProgram.programId,
@@ -90,23 +179,21 @@ describe("spinwheel", () => {
     const initialPlayerBalance = await provider.connection.getBalance(player.publicKey);
     const initialVaultBalance = await provider.connection.getBalance(houseVaultPda);
 
-    const tx = await program.methods
-      .spin(betAmount, prediction)
-      .accounts({
-        gameState: gameStatePda,
-        houseVault: houseVaultPda,
-        player: player.publicKey,
-        recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
-        systemProgram: web3.SystemProgram.programId,
-      })
-      .signers([player])
-      .rpc();
+    const houseSeed = await requestSpin(player, betAmount, prediction);
+
+    // Nothing is settled until the house fulfils the spin
+    let gameStateAccount = await program.account.gameState.fetch(gameStatePda);
+    expect(gameStateAccount.totalGames.toNumber()).to.equal(0);
+    expect(gameStateAccount.reserved.toNumber()).to.be.greaterThan(0);
 
-    console.log("Spin transaction signature:", tx);
+    const tx = await fulfilSpin(player.publicKey, houseSeed);
+    console.log("Fulfil spin transaction signature:", tx);
 
     // Check updated game state
-    const gameStateAccount = await program.account.gameState.fetch(gameStatePda);
+    gameStateAccount = await program.account.gameState.fetch(gameStatePda);
     expect(gameStateAccount.totalGames.toNumber()).to.equal(1);
+    expect(gameStateAccount.reserved.toNumber()).to.equal(0);
+    expect(await provider.connection.getAccountInfo(pendingSpinPda(player.publicKey))).to.be.null;
     expect(gameStateAccount.totalWagered.toNumber()).to.equal(betAmount.toNumber());
 
     // Check player and vault balances
@@ -134,17 +221,8 @@ describe("spinwheel", () => {
     for (let i = 0; i < 5; i++) {
       const prediction = i % 8;
       try {
-        const tx = await program.methods
-          .spin(betAmount, prediction)
-          .accounts({
-            gameState: gameStatePda,
-            houseVault: houseVaultPda,
-            player: player.publicKey,
-            recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
-            systemProgram: web3.SystemProgram.programId,
-          })
-          .signers([player])
-          .rpc();
+        const houseSeed = await requestSpin(player, betAmount, prediction);
+        const tx = await fulfilSpin(player.publicKey, houseSeed);
         console.log(`Spin ${i + 1} transaction:`, tx);
       } catch (error) {
         console.error(`Spin ${i + 1} failed:`, error);
@@ -161,6 +239,98 @@ describe("spinwheel", () => {
     expect(gameStateAccount.totalWagered.toNumber()).to.equal(betAmount.toNumber() * 5 + 100_000_000); // Includes previous spin
   });
 
+  it("Configures a weighted wheel and pays the segment multiplier", async () => {
+    // Money Wheel style: common low payers, rare high payers
+    const layout = [
+      ...Array(6).fill({ weight: 4, multiplier: 2 }),
+      ...Array(3).fill({ weight: 2, multiplier: 3 }),
+      { weight: 1, multiplier: 6 },
+      { weight: 1, multiplier: 11 },
+      { weight: 1, multiplier: 41 },
+    ];
+    await program.methods
+      .configureWheel(layout)
+      .accounts({
+        gameState: gameStatePda,
+        wheelConfig: wheelConfigPda,
+        authority: authority.publicKey,
+      })
+      .rpc();
+
+    const wheelConfig = await program.account.wheelConfig.fetch(wheelConfigPda);
+    expect(wheelConfig.segments.length).to.equal(12);
+    expect(wheelConfig.totalWeight).to.equal(33);
+    expect(wheelConfig.maxMultiplier).to.equal(41);
+
+    const player = web3.Keypair.generate();
+    await confirmAirdrop(player.publicKey, web3.LAMPORTS_PER_SOL);
+    const betAmount = new BN(10_000_000);
+    const paidBefore = (await program.account.gameState.fetch(gameStatePda)).totalPaidOut.toNumber();
+
+    const houseSeed = await requestSpin(player, betAmount, 11);
+    const { houseEdge, reserved } = await program.account.gameState.fetch(gameStatePda);
+    const payoutIfWon = Math.floor((betAmount.toNumber() * 41 * (100 - houseEdge)) / 100);
+    expect(reserved.toNumber()).to.equal(payoutIfWon);
+
+    await fulfilSpin(player.publicKey, houseSeed);
+    const gameStateAccount = await program.account.gameState.fetch(gameStatePda);
+    expect(gameStateAccount.reserved.toNumber()).to.equal(0);
+    expect([0, payoutIfWon]).to.include(gameStateAccount.totalPaidOut.toNumber() - paidBefore);
+  });
+
+  it("Rejects invalid wheel layouts", async () => {
+    const layouts = [
+      [{ weight: 1, multiplier: 2 }], // too few segments
+      [{ weight: 1, multiplier: 2 }, { weight: 0, multiplier: 2 }], // zero weight
+      [{ weight: 1, multiplier: 0 }, { weight: 1, multiplier: 0 }], // nothing pays
+    ];
+    for (const layout of layouts) {
+      try {
+        await program.methods
+          .configureWheel(layout)
+          .accounts({
+            gameState: gameStatePda,
+            wheelConfig: wheelConfigPda,
+            authority: authority.publicKey,
+          })
+          .rpc();
+        expect.fail("Should have failed with InvalidWheel");
+      } catch (error) {
+        expect(error.toString()).to.include("InvalidWheel");
+      }
+    }
+  });
+
+  it("Rejects a house seed that does not match the commitment", async () => {
+    const player = web3.Keypair.generate();
+    await confirmAirdrop(player.publicKey, web3.LAMPORTS_PER_SOL);
+
+    await requestSpin(player, new BN(10_000_000), 2);
+
+    try {
+      await fulfilSpin(player.publicKey, crypto.randomBytes(32));
+      expect.fail("Should have failed with InvalidHouseSeed");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidHouseSeed");
+    }
+
+    // Anyone can crank a refund, but not while the house can still fulfil
+    try {
+      await program.methods
+        .refundSpin()
+        .accounts({
+          gameState: gameStatePda,
+          houseVault: houseVaultPda,
+          pendingSpin: pendingSpinPda(player.publicKey),
+          player: player.publicKey,
+        })
+        .rpc();
+      expect.fail("Should have failed with FulfilWindowOpen");
+    } catch (error) {
+      expect(error.toString()).to.include("FulfilWindowOpen");
+    }
+  });
+
   it("Updates game settings", async () => {
     const newHouseEdge = 8;
     const newMinBet = new BN(500_000); // 0.0005 SOL
@@ -201,17 +371,7 @@ describe("spinwheel", () => {
     await confirmAirdrop(player.publicKey, web3.LAMPORTS_PER_SOL);
 
     try {
-      await program.methods
-        .spin(new BN(500_000), 3)
-        .accounts({
-          gameState: gameStatePda,
-          houseVault: houseVaultPda,
-          player: player.publicKey,
-          recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
-          systemProgram: web3.SystemProgram.programId,
-        })
-        .signers([player])
-        .rpc();
+      await requestSpin(player, new BN(500_000), 3);
       expect.fail("Spin should have failed when game is paused");
     } catch (error) {
       expect(error.toString()).to.include("GamePaused");
@@ -293,75 +453,934 @@ describe("spinwheel", () => {
     await confirmAirdrop(player.publicKey, web3.LAMPORTS_PER_SOL);
 
     const gameStateAccount = await program.account.gameState.fetch(gameStatePda);
+    const wheelConfig = await program.account.wheelConfig.fetch(wheelConfigPda);
 
     // Test bet too low
     try {
-      await program.methods
-        .spin(new BN(gameStateAccount.minBet.toNumber() - 1), 0)
-        .accounts({
-          gameState: gameStatePda,
-          houseVault: houseVaultPda,
-          player: player.publicKey,
-          recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
-          systemProgram: web3.SystemProgram.programId,
-        })
-        .signers([player])
-        .rpc();
+      await requestSpin(player, new BN(gameStateAccount.minBet.toNumber() - 1), 0);
       expect.fail("Should have failed with BetTooLow");
     } catch (error) {
       expect(error.toString()).to.include("BetTooLow");
     }
 
     // Test bet too high
+    try {
+      await requestSpin(player, new BN(gameStateAccount.maxBet.toNumber() + 1), 0);
+      expect.fail("Should have failed with BetTooHigh");
+    } catch (error) {
+      expect(error.toString()).to.include("BetTooHigh");
+    }
+
+    // Test invalid prediction
+    try {
+      await requestSpin(player, new BN(gameStateAccount.minBet.toNumber()), wheelConfig.segments.length);
+      expect.fail("Should have failed with InvalidPrediction");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidPrediction");
+    }
+  });
+
+  it("Fails to fund with zero amount", async () => {
     try {
       await program.methods
-        .spin(new BN(gameStateAccount.maxBet.toNumber() + 1), 0)
+        .fundHouseVault(new BN(0))
         .accounts({
           gameState: gameStatePda,
           houseVault: houseVaultPda,
-          player: player.publicKey,
-          recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
+          funder: authority.publicKey,
           systemProgram: web3.SystemProgram.programId,
         })
-        .signers([player])
         .rpc();
-      expect.fail("Should have failed with BetTooHigh");
+      expect.fail("Should have failed with InvalidAmount");
     } catch (error) {
-      expect(error.toString()).to.include("BetTooHigh");
+      expect(error.toString()).to.include("InvalidAmount");
     }
+  });
 
-    // Test invalid prediction
+  it("Runs an independent table for another operator", async () => {
+    const operator = web3.Keypair.generate();
+    await confirmAirdrop(operator.publicKey, 3 * web3.LAMPORTS_PER_SOL);
+    const table = tablePdas(7, operator.publicKey);
+
+    await program.methods
+      .initialize(new BN(7), 10)
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        wheelConfig: table.wheelConfig,
+        authority: operator.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([operator])
+      .rpc();
+    await program.methods
+      .fundHouseVault(new BN(web3.LAMPORTS_PER_SOL))
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        funder: operator.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([operator])
+      .rpc();
+
+    const tableState = await program.account.gameState.fetch(table.gameState);
+    expect(tableState.tableId.toNumber()).to.equal(7);
+    expect(tableState.authority.toString()).to.equal(operator.publicKey.toString());
+    expect(tableState.houseEdge).to.equal(10);
+
+    // The operator's limits apply only to their own table
+    await program.methods
+      .updateSettings(null, new BN(5_000_000), null, null)
+      .accounts({ gameState: table.gameState, authority: operator.publicKey })
+      .signers([operator])
+      .rpc();
+    const mainState = await program.account.gameState.fetch(gameStatePda);
+    expect(mainState.minBet.toNumber()).to.not.equal(5_000_000);
+
+    // The main table's authority has no say over the operator's table
     try {
       await program.methods
-        .spin(new BN(gameStateAccount.minBet.toNumber()), 8)
+        .updateSettings(null, null, null, true)
+        .accounts({ gameState: table.gameState, authority: authority.publicKey })
+        .rpc();
+      expect.fail("Should have failed for a foreign authority");
+    } catch (error) {
+      expect(error.toString()).to.include("ConstraintRaw");
+    }
+
+    // Spins on the operator's table are co-signed by the operator and leave the main table alone
+    const player = web3.Keypair.generate();
+    await confirmAirdrop(player.publicKey, web3.LAMPORTS_PER_SOL);
+    const houseSeed = crypto.randomBytes(32);
+    const [pendingSpin] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("spin"), table.gameState.toBuffer(), player.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .spin(new BN(5_000_000), 3, Array.from(sha256([houseSeed])), Array.from(crypto.randomBytes(32)))
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        wheelConfig: table.wheelConfig,
+        pendingSpin,
+        player: player.publicKey,
+        house: operator.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([player, operator])
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 500));
+    await program.methods
+      .fulfilSpin(Array.from(houseSeed))
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        wheelConfig: table.wheelConfig,
+        pendingSpin,
+        player: player.publicKey,
+        house: operator.publicKey,
+        recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
+      })
+      .signers([operator])
+      .rpc();
+
+    expect((await program.account.gameState.fetch(table.gameState)).totalGames.toNumber()).to.equal(1);
+    expect((await program.account.gameState.fetch(gameStatePda)).totalGames.toNumber()).to.equal(
+      mainState.totalGames.toNumber()
+    );
+  });
+
+  it("Spins in a whitelisted SPL mint on the table's token vault", async () => {
+    const mint = await createMint(provider, authority.payer);
+    const [tokenTablePda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_table"), gameStatePda.toBuffer(), mint.toBuffer()],
+      program.programId
+    );
+    const [tokenVaultPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_vault"), tokenTablePda.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializeTokenTable(new BN(1_000), new BN(1_000_000))
+      .accounts({
+        gameState: gameStatePda,
+        tokenTable: tokenTablePda,
+        tokenVault: tokenVaultPda,
+        mint,
+        authority: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const houseTokens = await createTokenAccount(provider, authority.payer, mint, authority.publicKey);
+    await mintTo(provider, authority.payer, mint, houseTokens, 100_000_000);
+    await program.methods
+      .fundTokenVault(new BN(100_000_000))
+      .accounts({
+        tokenTable: tokenTablePda,
+        tokenVault: tokenVaultPda,
+        mint,
+        funderTokenAccount: houseTokens,
+        funder: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const player = web3.Keypair.generate();
+    await confirmAirdrop(player.publicKey, web3.LAMPORTS_PER_SOL);
+    const playerTokens = await createTokenAccount(provider, authority.payer, mint, player.publicKey);
+    await mintTo(provider, authority.payer, mint, playerTokens, 1_000_000);
+    const token = {
+      tokenTable: tokenTablePda,
+      vaultTokenAccount: tokenVaultPda,
+      playerTokenAccount: playerTokens,
+      mint,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    const betAmount = new BN(100_000);
+    const houseSeed = crypto.randomBytes(32);
+    const solWagered = (await program.account.gameState.fetch(gameStatePda)).totalWagered.toNumber();
+    await program.methods
+      .spin(betAmount, 0, Array.from(sha256([houseSeed])), Array.from(crypto.randomBytes(32)))
+      .accounts({
+        gameState: gameStatePda,
+        houseVault: houseVaultPda,
+        wheelConfig: wheelConfigPda,
+        pendingSpin: pendingSpinPda(player.publicKey),
+        player: player.publicKey,
+        house: authority.publicKey,
+        token,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([player, authority.payer])
+      .rpc();
+    expect(await tokenBalance(provider, playerTokens)).to.equal(1_000_000 - betAmount.toNumber());
+    expect((await program.account.pendingSpin.fetch(pendingSpinPda(player.publicKey))).mint.toString()).to.equal(
+      mint.toString()
+    );
+
+    await new Promise((resolve) => setTimeout(resolve, 500));
+    await program.methods
+      .fulfilSpin(Array.from(houseSeed))
+      .accounts({
+        gameState: gameStatePda,
+        houseVault: houseVaultPda,
+        wheelConfig: wheelConfigPda,
+        pendingSpin: pendingSpinPda(player.publicKey),
+        player: player.publicKey,
+        house: authority.publicKey,
+        recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
+        token,
+      })
+      .rpc();
+
+    // Token spins are tallied on the token table, not in the SOL totals
+    const tokenTable = await program.account.tokenTable.fetch(tokenTablePda);
+    expect(tokenTable.reserved.toNumber()).to.equal(0);
+    expect(tokenTable.totalWagered.toNumber()).to.equal(betAmount.toNumber());
+    expect(await tokenBalance(provider, playerTokens)).to.equal(
+      1_000_000 - betAmount.toNumber() + tokenTable.totalPaidOut.toNumber()
+    );
+    expect((await program.account.gameState.fetch(gameStatePda)).totalWagered.toNumber()).to.equal(solWagered);
+
+    // The player's stats keep the token spin in its own currency
+    const stats = await program.account.playerStats.fetch(
+      web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("player"), gameStatePda.toBuffer(), player.publicKey.toBuffer()],
+        program.programId
+      )[0]
+    );
+    expect(stats.currencies).to.have.length(1);
+    expect(stats.currencies[0].mint.toString()).to.equal(mint.toString());
+    expect(stats.currencies[0].gamesPlayed.toNumber()).to.equal(1);
+    expect(stats.currencies[0].totalWagered.toNumber()).to.equal(betAmount.toNumber());
+    expect(stats.currencies[0].totalWon.toNumber()).to.equal(tokenTable.totalPaidOut.toNumber());
+  });
+
+  it("Refuses to whitelist a mint that charges a transfer fee", async () => {
+    const mint = await createTransferFeeMint(provider, authority.payer, 100, 1_000_000);
+    const [tokenTablePda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_table"), gameStatePda.toBuffer(), mint.toBuffer()],
+      program.programId
+    );
+    const [tokenVaultPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_vault"), tokenTablePda.toBuffer()],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .initializeTokenTable(new BN(1_000), new BN(1_000_000))
         .accounts({
           gameState: gameStatePda,
-          houseVault: houseVaultPda,
-          player: player.publicKey,
-          recentSlothashes: web3.SYSVAR_SLOT_HASHES_PUBKEY,
+          tokenTable: tokenTablePda,
+          tokenVault: tokenVaultPda,
+          mint,
+          authority: authority.publicKey,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
           systemProgram: web3.SystemProgram.programId,
         })
-        .signers([player])
         .rpc();
-      expect.fail("Should have failed with InvalidPrediction");
+      expect.fail("Should have rejected a transfer-fee mint");
     } catch (error) {
-      expect(error.toString()).to.include("InvalidPrediction");
+      expect(error.toString()).to.include("TransferFeeMint");
     }
   });
 
-  it("Fails to fund with zero amount", async () => {
+  it("Keeps each table id in its creator's namespace", async () => {
+    const squatter = web3.Keypair.generate();
+    await confirmAirdrop(squatter.publicKey, web3.LAMPORTS_PER_SOL);
+    const initialize = (table: ReturnType<typeof tablePdas>) =>
+      program.methods
+        .initialize(new BN(TABLE_ID), 20)
+        .accounts({
+          gameState: table.gameState,
+          houseVault: table.houseVault,
+          wheelConfig: table.wheelConfig,
+          authority: squatter.publicKey,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([squatter])
+        .rpc();
+
+    // The main table's address is derived from its authority, so another signer cannot claim it
+    try {
+      await initialize(tablePdas(TABLE_ID));
+      expect.fail("Should have failed to open a table under another creator");
+    } catch (error) {
+      expect(error.toString()).to.include("ConstraintSeeds");
+    }
+
+    // The same id under the squatter's own key is a separate table
+    const own = tablePdas(TABLE_ID, squatter.publicKey);
+    await initialize(own);
+    expect(own.gameState.toString()).to.not.equal(gameStatePda.toString());
+    const ownState = await program.account.gameState.fetch(own.gameState);
+    expect(ownState.creator.toString()).to.equal(squatter.publicKey.toString());
+    const mainState = await program.account.gameState.fetch(gameStatePda);
+    expect(mainState.creator.toString()).to.equal(authority.publicKey.toString());
+    expect(mainState.houseEdge).to.not.equal(20);
+  });
+
+  it("Hands a table to a new authority in two steps", async () => {
+    const operator = web3.Keypair.generate();
+    const successor = web3.Keypair.generate();
+    await confirmAirdrop(operator.publicKey, web3.LAMPORTS_PER_SOL);
+    await confirmAirdrop(successor.publicKey, web3.LAMPORTS_PER_SOL);
+    const table = tablePdas(21, operator.publicKey);
+    await program.methods
+      .initialize(new BN(21), 5)
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        wheelConfig: table.wheelConfig,
+        authority: operator.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([operator])
+      .rpc();
+
+    await program.methods
+      .proposeAuthority(successor.publicKey)
+      .accounts({ gameState: table.gameState, authority: operator.publicKey })
+      .signers([operator])
+      .rpc();
+
+    // Proposing alone changes nothing, and nobody else can accept
+    let state = await program.account.gameState.fetch(table.gameState);
+    expect(state.authority.toString()).to.equal(operator.publicKey.toString());
+    expect(state.pendingAuthority.toString()).to.equal(successor.publicKey.toString());
     try {
       await program.methods
-        .fundHouseVault(new BN(0))
+        .acceptAuthority()
+        .accounts({ gameState: table.gameState, newAuthority: authority.publicKey })
+        .rpc();
+      expect.fail("Should have failed for a signer that was not proposed");
+    } catch (error) {
+      expect(error.toString()).to.include("NotPendingAuthority");
+    }
+
+    await program.methods
+      .acceptAuthority()
+      .accounts({ gameState: table.gameState, newAuthority: successor.publicKey })
+      .signers([successor])
+      .rpc();
+    state = await program.account.gameState.fetch(table.gameState);
+    expect(state.authority.toString()).to.equal(successor.publicKey.toString());
+    expect(state.pendingAuthority.toString()).to.equal(web3.PublicKey.default.toString());
+
+    // The old authority is locked out, the new one is in charge
+    try {
+      await program.methods
+        .updateSettings(null, null, null, true)
+        .accounts({ gameState: table.gameState, authority: operator.publicKey })
+        .signers([operator])
+        .rpc();
+      expect.fail("Should have failed for the previous authority");
+    } catch (error) {
+      expect(error.toString()).to.include("ConstraintRaw");
+    }
+    await program.methods
+      .updateSettings(null, null, null, true)
+      .accounts({ gameState: table.gameState, authority: successor.publicKey })
+      .signers([successor])
+      .rpc();
+    expect((await program.account.gameState.fetch(table.gameState)).isPaused).to.be.true;
+  });
+
+  it("Requires M-of-N admin approval once a multisig is enabled", async () => {
+    const table = tablePdas(22);
+    const admins = [web3.Keypair.generate(), web3.Keypair.generate(), web3.Keypair.generate()];
+    for (const admin of admins) {
+      await confirmAirdrop(admin.publicKey, web3.LAMPORTS_PER_SOL);
+    }
+    await program.methods
+      .initialize(new BN(22), 5)
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        wheelConfig: table.wheelConfig,
+        authority: authority.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods
+      .fundHouseVault(new BN(web3.LAMPORTS_PER_SOL))
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        funder: authority.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const [multisig] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig"), table.gameState.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .createMultisig(
+        admins.map((admin) => admin.publicKey),
+        2
+      )
+      .accounts({
+        gameState: table.gameState,
+        multisig,
+        authority: authority.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // The authority alone can no longer change settings
+    try {
+      await program.methods
+        .updateSettings(null, null, null, true)
+        .accounts({ gameState: table.gameState, authority: authority.publicKey })
+        .rpc();
+      expect.fail("Should have required a proposal");
+    } catch (error) {
+      expect(error.toString()).to.include("MultisigRequired");
+    }
+
+    const proposalPda = (id: number) =>
+      web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("proposal"), multisig.toBuffer(), new BN(id).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    const execute = (id: number, executor: web3.Keypair) =>
+      program.methods
+        .executeProposal()
         .accounts({
-          houseVault: houseVaultPda,
-          funder: authority.publicKey,
+          gameState: table.gameState,
+          multisig,
+          proposal: proposalPda(id),
+          proposer: admins[0].publicKey,
+          executor: executor.publicKey,
+          houseVault: table.houseVault,
+          authority: authority.publicKey,
+        })
+        .signers([executor])
+        .rpc();
+
+    // Proposal 0: pause the table. One approval (the proposer's) is not enough
+    await program.methods
+      .createProposal({ updateSettings: { houseEdge: null, minBet: null, maxBet: null, isPaused: true } })
+      .accounts({
+        multisig,
+        proposal: proposalPda(0),
+        proposer: admins[0].publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([admins[0]])
+      .rpc();
+    try {
+      await execute(0, admins[0]);
+      expect.fail("Should have needed a second approval");
+    } catch (error) {
+      expect(error.toString()).to.include("NotEnoughApprovals");
+    }
+    await program.methods
+      .approveProposal()
+      .accounts({ multisig, proposal: proposalPda(0), admin: admins[1].publicKey })
+      .signers([admins[1]])
+      .rpc();
+    await execute(0, admins[2]);
+    expect((await program.account.gameState.fetch(table.gameState)).isPaused).to.be.true;
+    expect(await provider.connection.getAccountInfo(proposalPda(0))).to.be.null;
+
+    // Proposal 1: withdraw to the authority once two admins agree
+    const withdrawAmount = new BN(web3.LAMPORTS_PER_SOL / 2);
+    await program.methods
+      .createProposal({ withdrawHouseFunds: { amount: withdrawAmount } })
+      .accounts({
+        multisig,
+        proposal: proposalPda(1),
+        proposer: admins[0].publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([admins[0]])
+      .rpc();
+    await program.methods
+      .approveProposal()
+      .accounts({ multisig, proposal: proposalPda(1), admin: admins[2].publicKey })
+      .signers([admins[2]])
+      .rpc();
+    const vaultBefore = await provider.connection.getBalance(table.houseVault);
+    await execute(1, admins[1]);
+    expect(await provider.connection.getBalance(table.houseVault)).to.equal(
+      vaultBefore - withdrawAmount.toNumber()
+    );
+
+    // Outsiders cannot approve
+    const outsider = web3.Keypair.generate();
+    await program.methods
+      .createProposal({ withdrawHouseFunds: { amount: withdrawAmount } })
+      .accounts({
+        multisig,
+        proposal: proposalPda(2),
+        proposer: admins[0].publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([admins[0]])
+      .rpc();
+    try {
+      await program.methods
+        .approveProposal()
+        .accounts({ multisig, proposal: proposalPda(2), admin: outsider.publicKey })
+        .signers([outsider])
+        .rpc();
+      expect.fail("Should have rejected a non-admin");
+    } catch (error) {
+      expect(error.toString()).to.include("NotAdmin");
+    }
+  });
+
+  it("Routes wheel, token table and authority changes through the multisig", async () => {
+    const table = tablePdas(24);
+    const admins = [web3.Keypair.generate(), web3.Keypair.generate()];
+    for (const admin of admins) {
+      await confirmAirdrop(admin.publicKey, web3.LAMPORTS_PER_SOL);
+    }
+    await program.methods
+      .initialize(new BN(24), 5)
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        wheelConfig: table.wheelConfig,
+        authority: authority.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    const [multisig] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("multisig"), table.gameState.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .createMultisig(
+        admins.map((admin) => admin.publicKey),
+        2
+      )
+      .accounts({
+        gameState: table.gameState,
+        multisig,
+        authority: authority.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const mint = await createMint(provider, authority.payer);
+    const [tokenTablePda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_table"), table.gameState.toBuffer(), mint.toBuffer()],
+      program.programId
+    );
+    const [tokenVaultPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_vault"), tokenTablePda.toBuffer()],
+      program.programId
+    );
+    const layout = [
+      { weight: 3, multiplier: 2 },
+      { weight: 1, multiplier: 5 },
+    ];
+    const successor = web3.Keypair.generate().publicKey;
+
+    // None of these admin actions is open to the authority alone any more
+    const direct = [
+      program.methods
+        .configureWheel(layout)
+        .accounts({ gameState: table.gameState, wheelConfig: table.wheelConfig, authority: authority.publicKey }),
+      program.methods.initializeTokenTable(new BN(1_000), new BN(1_000_000)).accounts({
+        gameState: table.gameState,
+        tokenTable: tokenTablePda,
+        tokenVault: tokenVaultPda,
+        mint,
+        authority: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: web3.SystemProgram.programId,
+      }),
+      program.methods
+        .proposeAuthority(successor)
+        .accounts({ gameState: table.gameState, authority: authority.publicKey }),
+    ];
+    for (const call of direct) {
+      try {
+        await call.rpc();
+        expect.fail("Should have required a proposal");
+      } catch (error) {
+        expect(error.toString()).to.include("MultisigRequired");
+      }
+    }
+
+    const proposalPda = (id: number) =>
+      web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("proposal"), multisig.toBuffer(), new BN(id).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    let nextProposal = 0;
+    const approve = async (action: any) => {
+      const id = nextProposal++;
+      await program.methods
+        .createProposal(action)
+        .accounts({
+          multisig,
+          proposal: proposalPda(id),
+          proposer: admins[0].publicKey,
           systemProgram: web3.SystemProgram.programId,
         })
+        .signers([admins[0]])
         .rpc();
-      expect.fail("Should have failed with InvalidAmount");
+      await program.methods
+        .approveProposal()
+        .accounts({ multisig, proposal: proposalPda(id), admin: admins[1].publicKey })
+        .signers([admins[1]])
+        .rpc();
+      return id;
+    };
+    const execute = (id: number, extra: object = {}) =>
+      program.methods
+        .executeProposal()
+        .accounts({
+          gameState: table.gameState,
+          multisig,
+          proposal: proposalPda(id),
+          proposer: admins[0].publicKey,
+          executor: admins[1].publicKey,
+          houseVault: table.houseVault,
+          authority: authority.publicKey,
+          ...extra,
+        })
+        .signers([admins[1]])
+        .rpc();
+
+    await execute(await approve({ configureWheel: { segments: layout } }), { wheelConfig: table.wheelConfig });
+    const wheelConfig = await program.account.wheelConfig.fetch(table.wheelConfig);
+    expect(wheelConfig.segments.length).to.equal(2);
+    expect(wheelConfig.maxMultiplier).to.equal(5);
+
+    const initId = await approve({
+      initializeTokenTable: { mint, minBet: new BN(1_000), maxBet: new BN(1_000_000) },
+    });
+    await program.methods
+      .executeInitializeTokenTable()
+      .accounts({
+        gameState: table.gameState,
+        multisig,
+        proposal: proposalPda(initId),
+        proposer: admins[0].publicKey,
+        tokenTable: tokenTablePda,
+        tokenVault: tokenVaultPda,
+        mint,
+        executor: admins[1].publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([admins[1]])
+      .rpc();
+    expect((await program.account.tokenTable.fetch(tokenTablePda)).maxBet.toNumber()).to.equal(1_000_000);
+
+    try {
+      await program.methods
+        .updateTokenTable(null, new BN(2_000_000))
+        .accounts({ gameState: table.gameState, tokenTable: tokenTablePda, authority: authority.publicKey })
+        .rpc();
+      expect.fail("Should have required a proposal");
     } catch (error) {
-      expect(error.toString()).to.include("InvalidAmount");
+      expect(error.toString()).to.include("MultisigRequired");
+    }
+    await execute(await approve({ updateTokenTable: { mint, minBet: null, maxBet: new BN(2_000_000) } }), {
+      tokenTable: tokenTablePda,
+    });
+    expect((await program.account.tokenTable.fetch(tokenTablePda)).maxBet.toNumber()).to.equal(2_000_000);
+
+    await execute(await approve({ proposeAuthority: { newAuthority: successor } }));
+    expect((await program.account.gameState.fetch(table.gameState)).pendingAuthority.toString()).to.equal(
+      successor.toString()
+    );
+  });
+
+  it("Timelocks house withdrawals above the threshold", async () => {
+    const table = tablePdas(23);
+    await program.methods
+      .initialize(new BN(23), 5)
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        wheelConfig: table.wheelConfig,
+        authority: authority.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods
+      .fundHouseVault(new BN(2 * web3.LAMPORTS_PER_SOL))
+      .accounts({
+        gameState: table.gameState,
+        houseVault: table.houseVault,
+        funder: authority.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    const settings = { gameState: table.gameState, authority: authority.publicKey };
+    await program.methods.configureWithdrawalTimelock(new BN(100_000_000), new BN(2)).accounts(settings).rpc();
+    await program.methods.updateSettings(null, null, null, true).accounts(settings).rpc();
+    const withdrawAccounts = { ...settings, houseVault: table.houseVault };
+
+    // Small withdrawals still go through directly, large ones must be queued
+    await program.methods.withdrawHouseFunds(new BN(50_000_000)).accounts(withdrawAccounts).rpc();
+    try {
+      await program.methods.withdrawHouseFunds(new BN(500_000_000)).accounts(withdrawAccounts).rpc();
+      expect.fail("Should have required queueing");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawalMustBeQueued");
+    }
+
+    // Repeated small withdrawals share one threshold per window
+    await program.methods.withdrawHouseFunds(new BN(50_000_000)).accounts(withdrawAccounts).rpc();
+    try {
+      await program.methods.withdrawHouseFunds(new BN(1)).accounts(withdrawAccounts).rpc();
+      expect.fail("Should have capped the window's unqueued withdrawals");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawalMustBeQueued");
+    }
+    expect((await program.account.gameState.fetch(table.gameState)).windowWithdrawn.toNumber()).to.equal(
+      100_000_000
+    );
+
+    // A queued withdrawal can be cancelled during its delay
+    await program.methods.queueWithdrawal(new BN(500_000_000)).accounts(settings).rpc();
+    await program.methods.cancelWithdrawal().accounts(settings).rpc();
+    expect((await program.account.gameState.fetch(table.gameState)).queuedWithdrawal.toNumber()).to.equal(0);
+
+    await program.methods.queueWithdrawal(new BN(500_000_000)).accounts(settings).rpc();
+    const queued = await program.account.gameState.fetch(table.gameState);
+    expect(queued.queuedWithdrawal.toNumber()).to.equal(500_000_000);
+    try {
+      await program.methods.queueWithdrawal(new BN(1)).accounts(settings).rpc();
+      expect.fail("Should allow only one queued withdrawal");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawalAlreadyQueued");
+    }
+    try {
+      await program.methods.executeWithdrawal().accounts(withdrawAccounts).rpc();
+      expect.fail("Should still be timelocked");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawalLocked");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+    const vaultBefore = await provider.connection.getBalance(table.houseVault);
+    await program.methods.executeWithdrawal().accounts(withdrawAccounts).rpc();
+    expect(await provider.connection.getBalance(table.houseVault)).to.equal(vaultBefore - 500_000_000);
+    const state = await program.account.gameState.fetch(table.gameState);
+    expect(state.queuedWithdrawal.toNumber()).to.equal(0);
+    expect(state.withdrawalUnlocksAt.toNumber()).to.equal(0);
+
+    // Token vault withdrawals wait out the same delay
+    const mint = await createMint(provider, authority.payer);
+    const [tokenTablePda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_table"), table.gameState.toBuffer(), mint.toBuffer()],
+      program.programId
+    );
+    const [tokenVaultPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("token_vault"), tokenTablePda.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeTokenTable(new BN(1_000), new BN(1_000_000))
+      .accounts({
+        gameState: table.gameState,
+        tokenTable: tokenTablePda,
+        tokenVault: tokenVaultPda,
+        mint,
+        authority: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    const houseTokens = await createTokenAccount(provider, authority.payer, mint, authority.publicKey);
+    await mintTo(provider, authority.payer, mint, houseTokens, 1_000_000);
+    await program.methods
+      .fundTokenVault(new BN(1_000_000))
+      .accounts({
+        tokenTable: tokenTablePda,
+        tokenVault: tokenVaultPda,
+        mint,
+        funderTokenAccount: houseTokens,
+        funder: authority.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    const tokenSettings = { gameState: table.gameState, tokenTable: tokenTablePda, authority: authority.publicKey };
+    const executeTokenWithdrawal = () =>
+      program.methods
+        .executeTokenWithdrawal()
+        .accounts({
+          gameState: table.gameState,
+          tokenTable: tokenTablePda,
+          tokenVault: tokenVaultPda,
+          mint,
+          authorityTokenAccount: houseTokens,
+          authority: authority.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    await program.methods.queueTokenWithdrawal(new BN(400_000)).accounts(tokenSettings).rpc();
+    try {
+      await executeTokenWithdrawal();
+      expect.fail("Token withdrawal should still be timelocked");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawalLocked");
+    }
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+    await executeTokenWithdrawal();
+    expect(await tokenBalance(provider, houseTokens)).to.equal(400_000);
+    expect((await program.account.tokenTable.fetch(tokenTablePda)).queuedWithdrawal.toNumber()).to.equal(0);
+
+    // Loosening the timelock waits out the delay in force, so it cannot open the vault at once
+    await program.methods
+      .configureWithdrawalTimelock(new BN("18446744073709551615"), new BN(0))
+      .accounts(settings)
+      .rpc();
+    const loosening = await program.account.gameState.fetch(table.gameState);
+    expect(loosening.withdrawalThreshold.toNumber()).to.equal(100_000_000);
+    expect(loosening.withdrawalDelay.toNumber()).to.equal(2);
+    expect(loosening.timelockChangeAt.toNumber()).to.be.greaterThan(0);
+    try {
+      await program.methods.withdrawHouseFunds(new BN(500_000_000)).accounts(withdrawAccounts).rpc();
+      expect.fail("Should still have required queueing");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawalMustBeQueued");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+    await program.methods.withdrawHouseFunds(new BN(500_000_000)).accounts(withdrawAccounts).rpc();
+    const loosened = await program.account.gameState.fetch(table.gameState);
+    expect(loosened.withdrawalDelay.toNumber()).to.equal(0);
+    expect(loosened.timelockChangeAt.toNumber()).to.equal(0);
+  });
+
+  it("Tracks per-player statistics", async () => {
+    const player = web3.Keypair.generate();
+    await confirmAirdrop(player.publicKey, web3.LAMPORTS_PER_SOL);
+    const [playerStatsPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("player"), gameStatePda.toBuffer(), player.publicKey.toBuffer()],
+      program.programId
+    );
+    const betAmount = new BN(10_000_000);
+
+    let houseSeed = await requestSpin(player, betAmount, 0);
+    let stats = await program.account.playerStats.fetch(playerStatsPda);
+    expect(stats.player.toString()).to.equal(player.publicKey.toString());
+    expect(stats.currencies).to.have.length(0); // counted once the spin is settled
+    expect(stats.lastSpinTimestamp.toNumber()).to.be.greaterThan(0);
+    await fulfilSpin(player.publicKey, houseSeed);
+
+    houseSeed = await requestSpin(player, betAmount, 0);
+    await fulfilSpin(player.publicKey, houseSeed);
+
+    stats = await program.account.playerStats.fetch(playerStatsPda);
+    const [sol] = stats.currencies;
+    expect(sol.mint.toString()).to.equal(web3.PublicKey.default.toString());
+    expect(sol.gamesPlayed.toNumber()).to.equal(2);
+    expect(sol.totalWagered.toNumber()).to.equal(2 * betAmount.toNumber());
+    expect(sol.biggestWin.toNumber()).to.be.at.most(sol.totalWon.toNumber());
+    expect(Math.abs(sol.currentStreak)).to.be.within(1, 2);
+    // Every winning spin on segment 0 pays the same, so wins and the streak must agree
+    const winPayout = sol.biggestWin.toNumber();
+    const wins = winPayout === 0 ? 0 : sol.totalWon.toNumber() / winPayout;
+    if (sol.currentStreak === 2) expect(wins).to.equal(2);
+    if (sol.currentStreak === -2) expect(wins).to.equal(0);
+  });
+
+  it("Enforces the player's session cooldown and tracks net losses", async () => {
+    const player = web3.Keypair.generate();
+    await confirmAirdrop(player.publicKey, web3.LAMPORTS_PER_SOL);
+    const [playerLimitsPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("limits"), player.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .setLimits({
+        currencies: [
+          {
+            mint: web3.PublicKey.default,
+            dailyLossLimit: new BN(web3.LAMPORTS_PER_SOL / 10),
+            weeklyLossLimit: new BN(0),
+            maxBet: new BN(0),
+          },
+        ],
+        sessionCooldown: new BN(3600),
+        excludedUntil: new BN(0),
+      })
+      .accounts({
+        playerLimits: playerLimitsPda,
+        player: player.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([player])
+      .rpc();
+
+    const betAmount = new BN(10_000_000);
+    const houseSeed = await requestSpin(player, betAmount, 0);
+    await fulfilSpin(player.publicKey, houseSeed);
+
+    // Net loss is the stake less whatever the spin paid back
+    const stats = await program.account.playerStats.fetch(
+      web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("player"), gameStatePda.toBuffer(), player.publicKey.toBuffer()],
+        program.programId
+      )[0]
+    );
+    const limits = await program.account.playerLimits.fetch(playerLimitsPda);
+    expect(limits.limits.losses[0].dayLoss.toNumber()).to.equal(betAmount.toNumber() - stats.currencies[0].totalWon.toNumber());
+
+    try {
+      await requestSpin(player, betAmount, 0);
+      expect.fail("Should have enforced the session cooldown");
+    } catch (error) {
+      expect(error.toString()).to.include("CooldownActive");
     }
   });
-});
\ No newline at end of file
+});
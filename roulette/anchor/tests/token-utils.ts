@@ -0,0 +1,140 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, Transaction, TransactionInstruction } from "@solana/web3.js";
+
+// Minimal SPL Token helpers built from raw instructions, so the tests need no extra packages
+export const TOKEN_PROGRAM_ID = anchor.utils.token.TOKEN_PROGRAM_ID;
+export const TOKEN_2022_PROGRAM_ID = new PublicKey("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const MINT_SIZE = 82;
+const ACCOUNT_SIZE = 165;
+// Token-2022 mint with a TransferFeeConfig extension: padded base, account type and one TLV entry
+const TRANSFER_FEE_MINT_SIZE = 278;
+
+async function createAccount(
+  provider: anchor.AnchorProvider,
+  payer: Keypair,
+  space: number,
+  init: (account: PublicKey) => TransactionInstruction[],
+  programId = TOKEN_PROGRAM_ID
+) {
+  const account = Keypair.generate();
+  const lamports = await provider.connection.getMinimumBalanceForRentExemption(space);
+  const tx = new Transaction().add(
+    SystemProgram.createAccount({
+      fromPubkey: payer.publicKey,
+      newAccountPubkey: account.publicKey,
+      lamports,
+      space,
+      programId,
+    }),
+    ...init(account.publicKey)
+  );
+  await provider.sendAndConfirm(tx, [payer, account]);
+  return account.publicKey;
+}
+
+/** InitializeMint2 (tag 20) with `payer` as mint authority and no freeze authority */
+function initializeMint(mint: PublicKey, payer: Keypair, decimals: number, programId: PublicKey) {
+  const data = Buffer.alloc(67);
+  data.writeUInt8(20, 0);
+  data.writeUInt8(decimals, 1);
+  payer.publicKey.toBuffer().copy(data, 2);
+  return new TransactionInstruction({
+    programId,
+    keys: [{ pubkey: mint, isSigner: false, isWritable: true }],
+    data,
+  });
+}
+
+export function createMint(provider: anchor.AnchorProvider, payer: Keypair, decimals = 6) {
+  return createAccount(provider, payer, MINT_SIZE, (mint) => [
+    initializeMint(mint, payer, decimals, TOKEN_PROGRAM_ID),
+  ]);
+}
+
+/** Token-2022 mint whose transfers are charged `feeBps`, capped at `maxFee` */
+export function createTransferFeeMint(
+  provider: anchor.AnchorProvider,
+  payer: Keypair,
+  feeBps: number,
+  maxFee: number,
+  decimals = 6
+) {
+  return createAccount(
+    provider,
+    payer,
+    TRANSFER_FEE_MINT_SIZE,
+    (mint) => {
+      // TransferFeeExtension (26) / InitializeTransferFeeConfig (0), with no fee or withdraw authority
+      const data = Buffer.alloc(14);
+      data.writeUInt8(26, 0);
+      data.writeUInt8(0, 1);
+      data.writeUInt8(0, 2);
+      data.writeUInt8(0, 3);
+      data.writeUInt16LE(feeBps, 4);
+      data.writeBigUInt64LE(BigInt(maxFee), 6);
+      return [
+        new TransactionInstruction({
+          programId: TOKEN_2022_PROGRAM_ID,
+          keys: [{ pubkey: mint, isSigner: false, isWritable: true }],
+          data,
+        }),
+        initializeMint(mint, payer, decimals, TOKEN_2022_PROGRAM_ID),
+      ];
+    },
+    TOKEN_2022_PROGRAM_ID
+  );
+}
+
+/** InitializeAccount3 (tag 18) for `owner` */
+export function createTokenAccount(
+  provider: anchor.AnchorProvider,
+  payer: Keypair,
+  mint: PublicKey,
+  owner: PublicKey
+) {
+  return createAccount(provider, payer, ACCOUNT_SIZE, (account) => {
+    const data = Buffer.alloc(33);
+    data.writeUInt8(18, 0);
+    owner.toBuffer().copy(data, 1);
+    return [
+      new TransactionInstruction({
+        programId: TOKEN_PROGRAM_ID,
+        keys: [
+          { pubkey: account, isSigner: false, isWritable: true },
+          { pubkey: mint, isSigner: false, isWritable: false },
+        ],
+        data,
+      }),
+    ];
+  });
+}
+
+/** MintTo (tag 7), signed by `payer` as mint authority */
+export async function mintTo(
+  provider: anchor.AnchorProvider,
+  payer: Keypair,
+  mint: PublicKey,
+  destination: PublicKey,
+  amount: number
+) {
+  const data = Buffer.alloc(9);
+  data.writeUInt8(7, 0);
+  data.writeBigUInt64LE(BigInt(amount), 1);
+  const tx = new Transaction().add(
+    new TransactionInstruction({
+      programId: TOKEN_PROGRAM_ID,
+      keys: [
+        { pubkey: mint, isSigner: false, isWritable: true },
+        { pubkey: destination, isSigner: false, isWritable: true },
+        { pubkey: payer.publicKey, isSigner: true, isWritable: false },
+      ],
+      data,
+    })
+  );
+  await provider.sendAndConfirm(tx, [payer]);
+}
+
+export async function tokenBalance(provider: anchor.AnchorProvider, account: PublicKey) {
+  return Number((await provider.connection.getTokenAccountBalance(account)).value.amount);
+}